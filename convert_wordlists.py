@@ -4,13 +4,21 @@ import json
 input_file_path = 'assets/english.txt'
 output_file_path = 'assets/english.words.json'
 
+# Metadata of the language, the entry gets added to all.words.json under its id
+language_name = 'English'
+language_locales = ['en']
+
 # Read the input file
 with open(input_file_path, 'r', encoding='utf-8') as file:
     # Split the file content into a list of words, stripping newlines
     words = [line.strip() for line in file.readlines()]
 
 # Convert the list of words to a JSON format
-words_json = json.dumps(words, ensure_ascii=False, indent=4)
+words_json = json.dumps({
+    'name': language_name,
+    'locales': language_locales,
+    'words': words,
+}, ensure_ascii=False, indent=2)
 
 # Write the JSON data to the output file
 with open(output_file_path, 'w', encoding='utf-8') as file:
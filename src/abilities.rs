@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+
+use crate::{
+    enemy::{Enemy, Health},
+    states::{GameSetupSystemSet, GameSystemSet, MenuState},
+    typing::{Action, AddToType, WordSource},
+};
+
+/// Seconds until the screen can be cleared again
+const CLEAR_SCREEN_COOLDOWN: f32 = 60.;
+
+pub struct AbilityPlugin;
+impl Plugin for AbilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Ability>()
+            .add_systems(
+                OnExit(MenuState::MainMenu),
+                spawn_abilities.in_set(GameSetupSystemSet),
+            )
+            .add_systems(
+                Update,
+                (tick_abilities, insert_ability_typing.after(tick_abilities)).in_set(GameSystemSet),
+            )
+            .add_observer(clear_screen);
+    }
+}
+
+/// An ability, that can be used by typing a phrase once its cooldown is over
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+#[repr(transparent)]
+pub struct Ability {
+    cooldown: Timer,
+}
+impl Ability {
+    pub fn new(cooldown: f32) -> Self {
+        Self {
+            cooldown: Timer::from_seconds(cooldown, TimerMode::Once),
+        }
+    }
+}
+
+fn spawn_abilities(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Clear screen ability"),
+        Ability::new(CLEAR_SCREEN_COOLDOWN),
+        // Bottom of the map
+        Transform::from_xyz(0., -250., 0.),
+        Visibility::default(),
+    ));
+}
+
+fn tick_abilities(mut abilities: Query<&mut Ability>, time: Res<Time>) {
+    for mut ability in &mut abilities {
+        ability.cooldown.tick(time.delta());
+    }
+}
+
+fn insert_ability_typing(
+    abilities: Query<(Entity, &Ability, Option<&Children>)>,
+    mut commands: Commands,
+) {
+    for (entity, ability, children) in &abilities {
+        if ability.cooldown.finished() && children.map_or(true, |children| children.is_empty()) {
+            commands.trigger_targets(
+                AddToType(Action::ClearScreen(entity), WordSource::Phrase),
+                entity,
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct ClearScreen;
+
+/// Kills every enemy, and restarts the cooldown of the ability
+fn clear_screen(
+    trigger: Trigger<ClearScreen>,
+    mut abilities: Query<&mut Ability>,
+    mut enemies: Query<&mut Health, With<Enemy>>,
+) {
+    let mut ability = abilities
+        .get_mut(trigger.entity())
+        .expect("Entity used to trigger this function should be an ability");
+    ability.cooldown.reset();
+
+    for mut health in &mut enemies {
+        health.kill();
+    }
+}
@@ -1,8 +1,11 @@
+#[cfg(not(target_family = "wasm"))]
+use std::{env, path::PathBuf};
+
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 use bevy_common_assets::json::JsonAssetPlugin;
 
-use crate::{states::GameState, typing::Wordlists};
+use crate::{directors::Waves, states::GameState, wordlists::Wordlists};
 
 #[derive(Resource, Debug, Default, AssetCollection)]
 pub struct Handles {
@@ -12,6 +15,9 @@ pub struct Handles {
     #[asset(path = "all.words.json")]
     pub wordlists: Handle<Wordlists>,
 
+    #[asset(path = "level1.waves.json")]
+    pub waves: Handle<Waves>,
+
     #[asset(path = "level1.png")]
     pub level1: Handle<Image>,
 
@@ -53,6 +59,22 @@ impl Plugin for AssetLoaderPlugin {
                     .continue_to_state(GameState::Menu)
                     .load_collection::<Handles>(),
             )
-            .add_plugins(JsonAssetPlugin::<Wordlists>::new(&["words.json"]));
+            .add_plugins((
+                JsonAssetPlugin::<Wordlists>::new(&["words.json"]),
+                JsonAssetPlugin::<Waves>::new(&["waves.json"]),
+            ));
     }
 }
+
+/// The directory user files (custom wordlists, recordings) are stored in
+#[cfg(not(target_family = "wasm"))]
+pub fn data_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let data_dir = env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(not(target_os = "windows"))]
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+
+    data_dir.map(|data_dir| data_dir.join("typingTD"))
+}
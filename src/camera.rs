@@ -11,7 +11,16 @@ impl Plugin for CameraPlugin {
 }
 
 // Systems
-fn spawn_camera(mut commands: Commands, images: Res<Assets<Image>>, handles: Res<Handles>) {
+fn spawn_camera(
+    mut commands: Commands,
+    images: Res<Assets<Image>>,
+    handles: Res<Handles>,
+    cameras: Query<(), With<Camera2d>>,
+) {
+    // The main menu can be entered again from the practice menu
+    if !cameras.is_empty() {
+        return;
+    }
     let image = images.get(&handles.level1).expect("Image should be loaded");
 
     commands.spawn((Name::new("Camera"), Camera2d, OrthographicProjection {
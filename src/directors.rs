@@ -1,10 +1,18 @@
+use std::{collections::VecDeque, f64::consts::LN_2, fmt::Display};
+
 use bevy::prelude::*;
-use rand::{Rng, thread_rng};
+use rand::{Rng, distributions::WeightedIndex, prelude::Distribution};
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
 
 use crate::{
-    enemy::{Enemy, SpawnEnemy},
+    asset_loader::Handles,
+    enemy::{Enemy, Life, Money, SpawnEnemy},
     physics::apply_position,
-    states::GameSystemSet,
+    rng::{GameRng, RngStream},
+    states::{GameSetupSystemSet, GameSystemSet, MenuState},
+    stats::TypingStats,
+    typing::{MistypePolicy, MistypePolicyChosen},
 };
 
 pub struct DirectorPlugin;
@@ -12,61 +20,491 @@ impl Plugin for DirectorPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Difficulty>()
             .init_resource::<Director>()
+            .init_resource::<WavePlayer>()
+            .init_resource::<DirectorMode>()
+            .init_resource::<AdaptiveDirector>()
             .register_type::<Director>()
             .register_type::<Difficulty>()
+            .register_type::<WavePlayer>()
+            .register_type::<DirectorMode>()
+            .register_type::<AdaptiveDirector>()
+            .add_systems(
+                OnExit(MenuState::MainMenu),
+                (reset_waves, reset_adaptive_director, apply_difficulty_rules)
+                    .in_set(GameSetupSystemSet),
+            )
             .add_systems(
                 Update,
-                (update_director, spawn_enemies.before(apply_position)).in_set(GameSystemSet),
-            );
+                (
+                    play_waves.before(apply_position),
+                    update_adaptive_director
+                        .before(update_director)
+                        .run_if(resource_equals(DirectorMode::Adaptive)),
+                    // Generate enemies once the authored waves are over
+                    (update_director, spawn_enemies.before(apply_position)).run_if(waves_finished),
+                )
+                    .in_set(GameSystemSet),
+            )
+            .add_observer(change_director_mode)
+            .add_observer(change_difficulty);
     }
 }
 
 #[derive(Resource, Debug, Clone, Reflect)]
 #[reflect(Resource)]
-struct Director {
+pub struct Director {
     credits: f64,
     credit_rate: f64,
+    /// Average spawn attempts per second, at normal difficulty
+    spawn_rate: f64,
+    /// Spawn attempts until the next one, counted down with the spawn rate
+    next_attempt: f64,
+    /// The enemy spawned once the director can afford it
+    next_enemy: Option<Enemy>,
 }
 impl Default for Director {
     fn default() -> Self {
         Self {
             credits: 0.,
             credit_rate: 1.,
+            spawn_rate: 0.6,
+            next_attempt: 0.,
+            next_enemy: None,
         }
     }
 }
+impl Director {
+    /// Samples the exponentially distributed gap between two spawn attempts, in attempts
+    fn attempt_gap(rng: &mut impl Rng) -> f64 {
+        -(1. - rng.gen_range(0_f64..1.)).ln()
+    }
+}
 
-#[derive(Resource, Debug, Clone, Reflect, Default)]
+#[derive(
+    Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect, Default, EnumIter, Serialize, Deserialize,
+)]
 #[reflect(Resource)]
-enum Difficulty {
+pub enum Difficulty {
     Easy,
     #[default]
     Normal,
     Hard,
 }
 impl Difficulty {
-    const fn multiplier(&self) -> f64 {
-        match *self {
+    const fn multiplier(self) -> f64 {
+        match self {
             Self::Easy => 0.5,
             Self::Normal => 1.,
             Self::Hard => 2.,
         }
     }
+    /// The rules a run of this difficulty is played with
+    pub const fn rules(self) -> Rules {
+        match self {
+            Self::Easy => Rules {
+                life: 30.,
+                money: 10.,
+                credit_growth: 0.04,
+                enemy_health: 0.75,
+                mistype_policy: MistypePolicy::Hold,
+            },
+            Self::Normal => Rules {
+                life: 20.,
+                money: 0.,
+                credit_growth: 0.1,
+                enemy_health: 1.,
+                mistype_policy: MistypePolicy::Reset,
+            },
+            Self::Hard => Rules {
+                life: 10.,
+                money: 0.,
+                credit_growth: 0.24,
+                enemy_health: 1.5,
+                mistype_policy: MistypePolicy::Reset,
+            },
+        }
+    }
+}
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Self::Easy => "Easy",
+            Self::Normal => "Normal",
+            Self::Hard => "Hard",
+        })
+    }
+}
+
+/// What a difficulty changes about a run
+#[derive(Debug, Clone, Copy)]
+pub struct Rules {
+    /// Life at the start of the run
+    pub life: f64,
+    /// Money at the start of the run
+    pub money: f64,
+    /// How much the credit rate of the director grows each second, in credits per second
+    pub credit_growth: f64,
+    /// The factor the health of enemies gets multiplied with
+    pub enemy_health: f64,
+    /// The mistype policy of the run, unless the player picked one
+    pub mistype_policy: MistypePolicy,
+}
+
+/// Sets up the run with the rules of the difficulty
+pub fn apply_difficulty_rules(
+    difficulty: Res<Difficulty>,
+    mut director: ResMut<Director>,
+    mut life: ResMut<Life>,
+    mut money: ResMut<Money>,
+) {
+    let rules = difficulty.rules();
+    *director = Director::default();
+    life.value = rules.life;
+    money.value = rules.money;
+}
+
+/// How the director scales the enemies it generates
+#[derive(
+    Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect, Default, EnumIter, Serialize, Deserialize,
+)]
+#[reflect(Resource)]
+pub enum DirectorMode {
+    /// Scales with the difficulty, no matter how the player is doing
+    #[default]
+    Fixed,
+    /// Scales with the players performance, to keep them near a target stress
+    Adaptive,
+}
+impl DirectorMode {
+    /// Returns the factor credits and spawn attempts get multiplied with
+    fn multiplier(self, difficulty: &Difficulty, adaptive: &AdaptiveDirector) -> f64 {
+        match self {
+            Self::Fixed => difficulty.multiplier(),
+            Self::Adaptive => adaptive.pressure,
+        }
+    }
+}
+impl Display for DirectorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Self::Fixed => "Fixed",
+            Self::Adaptive => "Adaptive",
+        })
+    }
+}
+
+/// Estimates how stressed the player is from lost lives, recent typing speed and accuracy, income
+/// and banked money, and raises or lowers the pressure to keep the stress near the target
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct AdaptiveDirector {
+    /// Between 0 (bored) and 1 (overwhelmed)
+    pub target_stress: f64,
+    /// How fast the pressure reacts to the stress
+    pub responsiveness: f64,
+    /// The current stress estimate, between 0 and 1
+    stress: f64,
+    /// Replaces the difficulty multiplier, and makes tough enemies more likely above 1
+    pressure: f64,
+    /// Lives lost recently, fades over time
+    recent_life_loss: f64,
+    /// The life at the last update, None at the start of a run
+    last_life: Option<f64>,
+    /// Money earned recently, fades over time
+    recent_income: f64,
+    /// The money at the last update, None at the start of a run
+    last_money: Option<f64>,
+}
+impl Default for AdaptiveDirector {
+    fn default() -> Self {
+        Self {
+            target_stress: 0.5,
+            responsiveness: 0.1,
+            stress: 0.,
+            pressure: 1.,
+            recent_life_loss: 0.,
+            last_life: None,
+            recent_income: 0.,
+            last_money: None,
+        }
+    }
+}
+impl AdaptiveDirector {
+    const MIN_PRESSURE: f64 = 0.25;
+    const MAX_PRESSURE: f64 = 4.;
+    /// Seconds until a lost life only counts half
+    const LIFE_LOSS_HALF_LIFE: f64 = 20.;
+    /// Recently lost lives that count as the highest danger
+    const DANGER_LIVES: f64 = 5.;
+    /// Words per minute at which the player counts as keeping up
+    const FAST_WPM: f64 = 60.;
+    /// Seconds until earned money only counts half
+    const INCOME_HALF_LIFE: f64 = 20.;
+    /// Money earned per second at which the player counts as comfortable
+    const RICH_INCOME: f64 = 1.;
+    /// Banked money at which the player counts as comfortable
+    const RICH_MONEY: f64 = 50.;
+
+    /// Picks a random enemy, tough enemies are more likely the higher the pressure
+    fn pick_enemy(&self, rng: &mut impl Rng) -> Enemy {
+        let enemies: Vec<Enemy> = Enemy::iter().collect();
+        let weights = enemies
+            .iter()
+            .map(|enemy| enemy.health().powf(self.pressure - 1.));
+        enemies[WeightedIndex::new(weights)
+            .expect("Weights should be positive")
+            .sample(rng)]
+    }
+}
+
+fn reset_adaptive_director(mut adaptive: ResMut<AdaptiveDirector>) {
+    *adaptive = AdaptiveDirector {
+        target_stress: adaptive.target_stress,
+        responsiveness: adaptive.responsiveness,
+        ..default()
+    };
+}
+
+fn update_adaptive_director(
+    mut adaptive: ResMut<AdaptiveDirector>,
+    life: Res<Life>,
+    money: Res<Money>,
+    stats: Res<TypingStats>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs_f64();
+
+    let lost = adaptive
+        .last_life
+        .map_or(0., |last_life| (last_life - life.value).max(0.));
+    adaptive.last_life = Some(life.value);
+    adaptive.recent_life_loss = adaptive.recent_life_loss
+        * 0.5_f64.powf(delta / AdaptiveDirector::LIFE_LOSS_HALF_LIFE)
+        + lost;
+
+    // Only money gained counts, spending it on towers doesn't make the player poorer
+    let earned = adaptive
+        .last_money
+        .map_or(0., |last_money| (money.value - last_money).max(0.));
+    adaptive.last_money = Some(money.value);
+    adaptive.recent_income =
+        adaptive.recent_income * 0.5_f64.powf(delta / AdaptiveDirector::INCOME_HALF_LIFE) + earned;
+    // The income per second that adds up to the recent income when it is kept up
+    let income_rate = adaptive.recent_income * LN_2 / AdaptiveDirector::INCOME_HALF_LIFE;
+
+    let danger = (adaptive.recent_life_loss / AdaptiveDirector::DANGER_LIVES).min(1.);
+    let sloppiness = 1. - stats.recent_accuracy();
+    let slowness = 1. - (stats.recent_wpm() / AdaptiveDirector::FAST_WPM).min(1.);
+    // A large bank relaxes the player as much as a steady income
+    let wealth = ((income_rate / AdaptiveDirector::RICH_INCOME).min(1.)
+        + (money.value / AdaptiveDirector::RICH_MONEY).min(1.))
+        / 2.;
+    let poverty = 1. - wealth;
+    // Losing lives matters most, the rest only hints at how the player is doing
+    adaptive.stress = 0.4 * danger + 0.2 * (sloppiness + slowness + poverty);
+
+    // Multiplicative, so the pressure changes at the same rate whether it is high or low
+    let change = (adaptive.target_stress - adaptive.stress) * adaptive.responsiveness * delta;
+    adaptive.pressure = (adaptive.pressure * change.exp()).clamp(
+        AdaptiveDirector::MIN_PRESSURE,
+        AdaptiveDirector::MAX_PRESSURE,
+    );
+}
+
+fn update_director(
+    mut director: ResMut<Director>,
+    difficulty: Res<Difficulty>,
+    mode: Res<DirectorMode>,
+    adaptive: Res<AdaptiveDirector>,
+    time: Res<Time>,
+) {
+    // The growth already depends on the difficulty, so only the credits are scaled
+    director.credit_rate += difficulty.rules().credit_growth * time.delta_secs_f64();
+    director.credits +=
+        director.credit_rate * mode.multiplier(&difficulty, &adaptive) * time.delta_secs_f64();
+}
+
+/// Attempts to spawn enemies as a Poisson process. The gaps between attempts and the order of
+/// the enemies are drawn independently of the frame rate, so a seed always spawns the same enemies
+fn spawn_enemies(
+    mut director: ResMut<Director>,
+    difficulty: Res<Difficulty>,
+    mode: Res<DirectorMode>,
+    adaptive: Res<AdaptiveDirector>,
+    mut rng: ResMut<GameRng>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    let rate = director.spawn_rate * mode.multiplier(&difficulty, &adaptive);
+    director.next_attempt -= rate * time.delta_secs_f64();
+
+    // Every attempt that was due since the last frame
+    while director.next_attempt <= 0. {
+        director.next_attempt += Director::attempt_gap(rng.stream(RngStream::Director));
+
+        // The enemy waits until it is affordable, so missed attempts don't draw new ones
+        let enemy = *director.next_enemy.get_or_insert_with(|| match *mode {
+            DirectorMode::Fixed => Enemy::random(rng.stream(RngStream::Enemies)),
+            DirectorMode::Adaptive => adaptive.pick_enemy(rng.stream(RngStream::Enemies)),
+        });
+        let enemy_cost = enemy.credit_cost();
+        if enemy_cost <= director.credits {
+            director.credits -= enemy_cost;
+            director.next_enemy = None;
+
+            commands.trigger(SpawnEnemy(enemy));
+        }
+    }
 }
 
-fn update_director(mut director: ResMut<Director>, difficulty: Res<Difficulty>, time: Res<Time>) {
-    director.credit_rate += 0.1 * difficulty.multiplier() * time.delta_secs_f64();
-    director.credits += director.credit_rate * difficulty.multiplier() * time.delta_secs_f64();
+/// The authored waves of a run, played in order before the director generates enemies
+#[derive(Default, Deserialize, Asset, Debug, TypePath)]
+#[serde(transparent)]
+pub struct Waves {
+    pub waves: Vec<Wave>,
 }
 
-fn spawn_enemies(mut director: ResMut<Director>, mut commands: Commands) {
-    if thread_rng().gen_range(0..100) == 69 {
-        let random_enemy = Enemy::random();
-        let random_enemy_cost = random_enemy.credit_cost();
-        if random_enemy_cost <= director.credits {
-            director.credits -= random_enemy_cost;
+#[derive(Deserialize, Debug, Clone)]
+pub struct Wave {
+    /// Seconds between clearing the last wave (or starting the run) and the start of this wave
+    pub delay: f64,
+    /// Money paid out once every enemy of the wave is gone
+    pub reward: f64,
+    pub groups: Vec<WaveGroup>,
+}
 
-            commands.trigger(SpawnEnemy(random_enemy));
+/// Enemies of the same type, spawned one after another
+#[derive(Deserialize, Debug, Clone)]
+pub struct WaveGroup {
+    pub enemy: Enemy,
+    pub count: u32,
+    /// Seconds between two enemies of the group
+    pub spacing: f64,
+    /// Seconds between the start of the wave and the first enemy of the group
+    #[serde(default)]
+    pub delay: f64,
+}
+
+/// How far the run is through the authored waves
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+struct WavePlayer {
+    /// Seconds the run has been running, pauses aren't counted
+    clock: f64,
+    /// The index of the next wave
+    next: usize,
+    /// Enemies of the current wave that still have to spawn, with their spawn times
+    pending: VecDeque<(f64, Enemy)>,
+    /// The reward of the current wave, None between waves
+    reward: Option<f64>,
+    /// The time the last wave was cleared at
+    cleared: f64,
+}
+impl Default for WavePlayer {
+    fn default() -> Self {
+        Self {
+            clock: 0.,
+            next: 0,
+            pending: VecDeque::new(),
+            reward: None,
+            cleared: 0.,
         }
     }
 }
+
+fn reset_waves(mut player: ResMut<WavePlayer>) {
+    *player = WavePlayer::default();
+}
+
+/// Run condition, true once every authored wave has been played
+fn waves_finished(
+    player: Res<WavePlayer>,
+    waves: Res<Assets<Waves>>,
+    handles: Res<Handles>,
+) -> bool {
+    let count = waves
+        .get(&handles.waves)
+        .map_or(0, |waves| waves.waves.len());
+    player.next >= count && player.reward.is_none()
+}
+
+/// Spawns the enemies of the current wave, pays out its reward once it is cleared, and starts
+/// the next wave after its delay
+fn play_waves(
+    mut player: ResMut<WavePlayer>,
+    mut money: ResMut<Money>,
+    waves: Res<Assets<Waves>>,
+    handles: Res<Handles>,
+    enemies: Query<(), With<Enemy>>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    player.clock += time.delta_secs_f64();
+    let now = player.clock;
+
+    let mut spawned = false;
+    while let Some(&(spawn_time, enemy)) = player.pending.front()
+        && spawn_time <= now
+    {
+        player.pending.pop_front();
+        commands.trigger(SpawnEnemy(enemy));
+        spawned = true;
+    }
+
+    // Enemies spawned this frame don't exist yet, so the wave can't be cleared until the next one
+    if !spawned
+        && player.pending.is_empty()
+        && enemies.is_empty()
+        && let Some(reward) = player.reward.take()
+    {
+        money.value += reward;
+        player.cleared = now;
+    }
+
+    if player.reward.is_none()
+        && let Some(wave) = waves
+            .get(&handles.waves)
+            .and_then(|waves| waves.waves.get(player.next))
+        && now >= player.cleared + wave.delay
+    {
+        let mut pending: Vec<_> = wave
+            .groups
+            .iter()
+            .flat_map(|group| {
+                (0..group.count).map(move |i| {
+                    (
+                        f64::from(i).mul_add(group.spacing, now + group.delay),
+                        group.enemy,
+                    )
+                })
+            })
+            .collect();
+        pending.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        player.pending = pending.into();
+        player.reward = Some(wave.reward);
+        player.next += 1;
+    }
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct ChangeDirectorMode(pub DirectorMode);
+
+fn change_director_mode(trigger: Trigger<ChangeDirectorMode>, mut mode: ResMut<DirectorMode>) {
+    *mode = trigger.event().0;
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct ChangeDifficulty(pub Difficulty);
+
+fn change_difficulty(
+    trigger: Trigger<ChangeDifficulty>,
+    mut difficulty: ResMut<Difficulty>,
+    mut policy: ResMut<MistypePolicy>,
+    chosen: Res<MistypePolicyChosen>,
+) {
+    *difficulty = trigger.event().0;
+    if !chosen.value {
+        *policy = difficulty.rules().mistype_policy;
+    }
+}
@@ -1,14 +1,18 @@
 use bevy::prelude::*;
-use rand::{Rng, thread_rng};
+use rand::Rng;
+use serde::Deserialize;
 use strum::{EnumCount, EnumIter};
 
 use crate::{
     asset_loader::Handles,
+    directors::Difficulty,
     map::TILE_SIZE,
     path::{Path, PathState, to_0_or_1},
     physics::{Layer, Obb, Position, Rotation, Velocity},
     projectile::Speed,
     states::GameSystemSet,
+    stats::ShowStats,
+    typing::Combo,
 };
 
 pub struct EnemyPlugin;
@@ -19,7 +23,6 @@ impl Plugin for EnemyPlugin {
             .register_type::<Attack>()
             .init_resource::<Money>()
             .init_resource::<Life>()
-            .add_systems(Startup, init_life)
             .add_systems(
                 Update,
                 (
@@ -33,19 +36,15 @@ impl Plugin for EnemyPlugin {
     }
 }
 
-fn init_life(mut life: ResMut<Life>) {
-    life.value = 20.;
-}
-
-#[derive(Component, Debug, Clone, Copy, Reflect, EnumIter, EnumCount)]
+#[derive(Component, Debug, Clone, Copy, Reflect, EnumIter, EnumCount, Deserialize)]
 #[reflect(Component)]
 pub enum Enemy {
     Base,
     Chunky,
 }
 impl Enemy {
-    pub fn random() -> Self {
-        match thread_rng().gen_range(0..Self::COUNT) {
+    pub fn random(rng: &mut impl Rng) -> Self {
+        match rng.gen_range(0..Self::COUNT) {
             0 => Self::Base,
             1 => Self::Chunky,
             _ => unreachable!(),
@@ -93,6 +92,9 @@ impl Health {
     pub const fn new(value: f64) -> Self {
         Self { value }
     }
+    pub const fn kill(&mut self) {
+        self.value = 0.;
+    }
 }
 
 #[derive(Resource, Debug, Clone, Reflect, Default)]
@@ -168,6 +170,7 @@ pub fn spawn_enemy(
     mut commands: Commands,
     path: Res<Path>,
     handles: Res<Handles>,
+    difficulty: Res<Difficulty>,
 ) {
     let variant = trigger.event().0;
     let enemy = handles.enemy.clone();
@@ -191,7 +194,7 @@ pub fn spawn_enemy(
         Position::new(path.parts[0] - 2. * to_0_or_1(path.parts[1] - path.parts[0]) * TILE_SIZE),
         Velocity::new(to_0_or_1(path.parts[1] - path.parts[0]) * enemy_speed),
         Layer::new(3.),
-        Health::new(variant.health()),
+        Health::new(variant.health() * difficulty.rules().enemy_health),
         variant,
         Speed::new(enemy_speed),
         PathState::new(1),
@@ -207,11 +210,12 @@ fn despawn_dead_entities(
     mut commands: Commands,
     enemies: Query<(&Health, Option<&Enemy>, Entity)>,
     mut money: ResMut<Money>,
+    combo: Res<Combo>,
 ) {
     for (health, enemy_type, entity) in &enemies {
         if health.value <= 0. {
             if let Some(enemy_type) = enemy_type {
-                money.value += enemy_type.value();
+                money.value += enemy_type.value() * combo.multiplier();
             }
             commands.entity(entity).despawn();
         }
@@ -280,4 +284,10 @@ fn spawn_death_menu(commands: &mut Commands, handles: &Handles) {
         },
         Transform::from_xyz(0., 0., 100.),
     ));
+    commands.trigger(ShowStats);
+    commands.trigger(Died);
 }
+
+/// Triggered when the last life is lost
+#[derive(Debug, Clone, Event)]
+pub struct Died;
@@ -0,0 +1,296 @@
+use std::fmt::Display;
+
+use bevy::prelude::*;
+use bevy_device_lang::get_lang;
+use serde::{Deserialize, Serialize};
+use strum::{EnumCount, EnumIter};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::states::GameState;
+
+pub struct KeyboardPlugin;
+impl Plugin for KeyboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KeyboardLayout>()
+            .init_resource::<LayoutScoring>()
+            .register_type::<KeyboardLayout>()
+            .register_type::<LayoutScoring>()
+            .add_systems(OnExit(GameState::Loading), set_device_layout)
+            .add_observer(change_keyboard_layout);
+    }
+}
+
+/// The keyboard layout the player types on, used to judge how hard a word is to type
+#[derive(
+    Resource,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Reflect,
+    Default,
+    EnumIter,
+    EnumCount,
+    Serialize,
+    Deserialize,
+)]
+#[reflect(Resource)]
+pub enum KeyboardLayout {
+    #[default]
+    Qwerty,
+    Qwertz,
+    Azerty,
+    Dvorak,
+    Colemak,
+}
+impl KeyboardLayout {
+    /// Extra cost of a key that needs shift
+    const SHIFT_COST: f64 = 0.5;
+    /// Extra cost of two different keys typed with the same finger
+    const SAME_FINGER_COST: f64 = 1.;
+    /// Extra cost per skipped row between two keys of the same hand
+    const ROW_JUMP_COST: f64 = 0.5;
+    /// Cost of a grapheme that isn't on the layout (dead keys, compose, IME)
+    const UNKNOWN_COST: f64 = 2.;
+
+    /// The rows of the layout, from the number row to the bottom row
+    pub const fn rows(self) -> &'static [Row; 4] {
+        match self {
+            Self::Qwerty => &QWERTY,
+            Self::Qwertz => &QWERTZ,
+            Self::Azerty => &AZERTY,
+            Self::Dvorak => &DVORAK,
+            Self::Colemak => &COLEMAK,
+        }
+    }
+    /// Returns the key that types the character, or None if it isn't on the layout
+    pub fn key(self, character: char) -> Option<Key> {
+        self.rows().iter().enumerate().find_map(|(row_index, row)| {
+            let (column, shift) = row
+                .keys
+                .chars()
+                .position(|key| key == character)
+                .map(|column| (column, false))
+                .or_else(|| {
+                    row.shifted
+                        .chars()
+                        .position(|key| key == character)
+                        .map(|column| (column, true))
+                })?;
+            Some(Key {
+                finger: Finger::from_column(column.saturating_sub(row.offset)),
+                row: row_index,
+                column,
+                shift,
+            })
+        })
+    }
+    /// Returns how hard the word is to type on this layout, per grapheme.
+    /// 1 means every grapheme is a single unshifted key without any awkward movements
+    pub fn difficulty(self, word: &str) -> f64 {
+        let mut total = 0.;
+        let mut count = 0.;
+        let mut previous: Option<Key> = None;
+
+        for grapheme in word.graphemes(true) {
+            count += 1.;
+            // Spaces are typed with the thumbs, and don't interfere with the other fingers
+            if grapheme == " " {
+                total += 1.;
+                previous = None;
+                continue;
+            }
+            let Some(key) = grapheme
+                .chars()
+                .next()
+                .and_then(|character| self.key(character))
+            else {
+                total += Self::UNKNOWN_COST;
+                previous = None;
+                continue;
+            };
+
+            total += 1.;
+            if key.shift {
+                total += Self::SHIFT_COST;
+            }
+            if let Some(previous) = previous
+                && previous != key
+            {
+                if previous.finger == key.finger {
+                    total += Self::SAME_FINGER_COST;
+                }
+                // Row jumps only hurt when the same hand has to move
+                if previous.finger.is_left() == key.finger.is_left() {
+                    let jump = previous.row.abs_diff(key.row).saturating_sub(1);
+                    total += Self::ROW_JUMP_COST * jump as f64;
+                }
+            }
+            previous = Some(key);
+        }
+
+        if count == 0. { 1. } else { total / count }
+    }
+}
+impl Display for KeyboardLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Self::Qwerty => "QWERTY",
+            Self::Qwertz => "German QWERTZ",
+            Self::Azerty => "AZERTY",
+            Self::Dvorak => "Dvorak",
+            Self::Colemak => "Colemak",
+        })
+    }
+}
+
+/// A row of a keyboard layout
+#[derive(Debug, Clone, Copy)]
+pub struct Row {
+    pub keys: &'static str,
+    /// The characters typed with shift, in the same order as the keys
+    pub shifted: &'static str,
+    /// The amount of keys left of the key typed with the left pinky in its home column
+    pub offset: usize,
+}
+impl Row {
+    const fn new(keys: &'static str, shifted: &'static str, offset: usize) -> Self {
+        Self {
+            keys,
+            shifted,
+            offset,
+        }
+    }
+}
+
+const QWERTY: [Row; 4] = [
+    Row::new("`1234567890-=", "~!@#$%^&*()_+", 1),
+    Row::new("qwertyuiop[]\\", "QWERTYUIOP{}|", 0),
+    Row::new("asdfghjkl;'", "ASDFGHJKL:\"", 0),
+    Row::new("zxcvbnm,./", "ZXCVBNM<>?", 0),
+];
+const QWERTZ: [Row; 4] = [
+    Row::new("^1234567890ß´", "°!\"§$%&/()=?`", 1),
+    Row::new("qwertzuiopü+", "QWERTZUIOPÜ*", 0),
+    Row::new("asdfghjklöä#", "ASDFGHJKLÖÄ'", 0),
+    Row::new("<yxcvbnm,.-", ">YXCVBNM;:_", 1),
+];
+const AZERTY: [Row; 4] = [
+    Row::new("²&é\"'(-è_çà)=", "²1234567890°+", 1),
+    Row::new("azertyuiop^$", "AZERTYUIOP¨£", 0),
+    Row::new("qsdfghjklmù*", "QSDFGHJKLM%µ", 0),
+    Row::new("<wxcvbn,;:!", ">WXCVBN?./§", 1),
+];
+const DVORAK: [Row; 4] = [
+    Row::new("`1234567890[]", "~!@#$%^&*(){}", 1),
+    Row::new("',.pyfgcrl/=\\", "\"<>PYFGCRL?+|", 0),
+    Row::new("aoeuidhtns-", "AOEUIDHTNS_", 0),
+    Row::new(";qjkxbmwvz", ":QJKXBMWVZ", 0),
+];
+const COLEMAK: [Row; 4] = [
+    Row::new("`1234567890-=", "~!@#$%^&*()_+", 1),
+    Row::new("qwfpgjluy;[]\\", "QWFPGJLUY:{}|", 0),
+    Row::new("arstdhneio'", "ARSTDHNEIO\"", 0),
+    Row::new("zxcvbkm,./", "ZXCVBKM<>?", 0),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Finger {
+    LeftPinky,
+    LeftRing,
+    LeftMiddle,
+    LeftIndex,
+    RightIndex,
+    RightMiddle,
+    RightRing,
+    RightPinky,
+}
+impl Finger {
+    /// Returns the finger that types the column when touch typing
+    const fn from_column(column: usize) -> Self {
+        match column {
+            0 => Self::LeftPinky,
+            1 => Self::LeftRing,
+            2 => Self::LeftMiddle,
+            3 | 4 => Self::LeftIndex,
+            5 | 6 => Self::RightIndex,
+            7 => Self::RightMiddle,
+            8 => Self::RightRing,
+            _ => Self::RightPinky,
+        }
+    }
+    pub const fn is_left(self) -> bool {
+        matches!(
+            self,
+            Self::LeftPinky | Self::LeftRing | Self::LeftMiddle | Self::LeftIndex
+        )
+    }
+}
+
+/// A key on a keyboard layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub finger: Finger,
+    /// 0 is the number row, 3 the bottom row
+    pub row: usize,
+    /// The position of the key in its row
+    pub column: usize,
+    pub shift: bool,
+}
+
+/// How much the layout difficulty of a word matters
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct LayoutScoring {
+    /// Words that are hard on the layout get picked more often with positive values, and less
+    /// often with negative values. 0 ignores the layout
+    pub selection: f64,
+    /// How much more damage hard words deal. 0 ignores the layout
+    pub reward: f64,
+}
+impl Default for LayoutScoring {
+    fn default() -> Self {
+        Self {
+            selection: 0.,
+            reward: 0.5,
+        }
+    }
+}
+impl LayoutScoring {
+    /// Returns the factor the weight of a word with the given layout difficulty gets multiplied
+    /// with
+    pub fn selection_weight(&self, difficulty: f64) -> f64 {
+        difficulty.powf(self.selection)
+    }
+    /// Returns the factor the effect of the typed word gets multiplied with
+    pub fn reward(&self, layout: KeyboardLayout, word: &str) -> f64 {
+        (layout.difficulty(word) - 1.).mul_add(self.reward, 1.)
+    }
+}
+
+/// Picks the usual layout of the device language
+fn set_device_layout(mut layout: ResMut<KeyboardLayout>) {
+    let Some(device_language) = get_lang() else {
+        return;
+    };
+
+    if ["de", "cs", "sk", "hu", "sl", "hr"]
+        .iter()
+        .any(|locale| device_language.starts_with(locale))
+    {
+        *layout = KeyboardLayout::Qwertz;
+    } else if device_language.starts_with("fr") {
+        *layout = KeyboardLayout::Azerty;
+    }
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct ChangeKeyboardLayout(pub KeyboardLayout);
+
+fn change_keyboard_layout(
+    trigger: Trigger<ChangeKeyboardLayout>,
+    mut layout: ResMut<KeyboardLayout>,
+) {
+    *layout = trigger.event().0;
+}
@@ -0,0 +1,236 @@
+use bevy::{
+    color::palettes::css::{GREEN, RED, YELLOW},
+    prelude::*,
+};
+
+use crate::{
+    keyboard::KeyboardLayout,
+    stats::TypingStats,
+    typing::{ToType, TypingTarget, read_input},
+};
+
+/// Seconds a wrongly pressed key stays red
+const FLASH_DURATION: f64 = 0.3;
+/// Side length of a key
+const KEY_SIZE: f32 = 36.;
+/// Horizontal shift of each row compared to the one above it
+const ROW_STAGGER: f32 = 12.;
+/// The row of the space bar, below the rows of the layout
+const SPACE_ROW: usize = 4;
+
+const KEY_COLOR: Color = Color::srgba(0.2, 0.2, 0.2, 0.6);
+
+pub struct KeyboardOverlayPlugin;
+impl Plugin for KeyboardOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KeyboardOverlay>()
+            .register_type::<KeyboardOverlay>()
+            .register_type::<KeyCap>()
+            .add_systems(
+                Update,
+                (
+                    spawn_keyboard_overlay.run_if(
+                        resource_changed::<KeyboardOverlay>.or(resource_changed::<KeyboardLayout>),
+                    ),
+                    update_keyboard_overlay
+                        .after(spawn_keyboard_overlay)
+                        .after(read_input),
+                ),
+            )
+            .add_observer(toggle_keyboard_overlay);
+    }
+}
+
+/// Shows a keyboard at the bottom of the screen, which highlights the next key of the targeted
+/// word, and flashes wrongly pressed keys
+#[derive(Resource, Debug, Clone, Reflect, Default)]
+#[reflect(Resource)]
+#[repr(transparent)]
+pub struct KeyboardOverlay {
+    pub visible: bool,
+}
+
+#[derive(Component, Debug, Clone, Reflect, Default)]
+#[reflect(Component)]
+struct KeyboardOverlayRoot;
+
+/// A key of the overlay
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+struct KeyCap {
+    row: usize,
+    column: usize,
+    /// Elapsed seconds until which the key is shown as wrongly pressed
+    flash_until: f64,
+}
+impl KeyCap {
+    const fn new(row: usize, column: usize) -> Self {
+        Self {
+            row,
+            column,
+            flash_until: 0.,
+        }
+    }
+}
+
+/// Returns the row and column of the key that types the grapheme, and whether it needs shift
+fn key_position(layout: KeyboardLayout, grapheme: &str) -> Option<(usize, usize, bool)> {
+    if grapheme == " " {
+        return Some((SPACE_ROW, 0, false));
+    }
+    grapheme
+        .chars()
+        .next()
+        .and_then(|character| layout.key(character))
+        .map(|key| (key.row, key.column, key.shift))
+}
+
+/// Respawns the overlay with the current layout, or removes it if it is hidden
+fn spawn_keyboard_overlay(
+    mut commands: Commands,
+    overlay: Res<KeyboardOverlay>,
+    layout: Res<KeyboardLayout>,
+    roots: Query<Entity, With<KeyboardOverlayRoot>>,
+) {
+    for root in &roots {
+        commands.entity(root).despawn_recursive();
+    }
+    if !overlay.visible {
+        return;
+    }
+
+    commands
+        .spawn((
+            Name::new("Keyboard overlay"),
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.),
+                width: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.),
+                ..default()
+            },
+            KeyboardOverlayRoot,
+        ))
+        .with_children(|parent| {
+            for (row_index, row) in layout.rows().iter().enumerate() {
+                parent
+                    .spawn(Node {
+                        column_gap: Val::Px(4.),
+                        margin: UiRect::left(Val::Px(row_index as f32 * ROW_STAGGER)),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (column, key) in row.keys.chars().enumerate() {
+                            spawn_key_cap(
+                                parent,
+                                KeyCap::new(row_index, column),
+                                key.to_string(),
+                                KEY_SIZE,
+                            );
+                        }
+                    });
+            }
+            spawn_key_cap(
+                parent,
+                KeyCap::new(SPACE_ROW, 0),
+                String::new(),
+                KEY_SIZE * 6.,
+            );
+        });
+}
+
+fn spawn_key_cap(parent: &mut ChildBuilder, key_cap: KeyCap, label: String, width: f32) {
+    parent
+        .spawn((
+            Node {
+                width: Val::Px(width),
+                height: Val::Px(KEY_SIZE),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(KEY_COLOR),
+            key_cap,
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new(label), TextFont::from_font_size(20.)));
+        });
+}
+
+/// Highlights the next key of the targeted `ToType`, or the next keys of all candidates if none is
+/// targeted, and flashes wrongly pressed keys red.
+/// Keys that need shift are highlighted yellow instead of green
+fn update_keyboard_overlay(
+    mut key_caps: Query<(&mut KeyCap, &mut BackgroundColor)>,
+    target: Res<TypingTarget>,
+    to_types: Query<&ToType>,
+    stats: Res<TypingStats>,
+    layout: Res<KeyboardLayout>,
+    mut seen_keystrokes: Local<usize>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs_f64();
+
+    // The stats got reset
+    if stats.keystrokes.len() < *seen_keystrokes {
+        *seen_keystrokes = 0;
+    }
+    for keystroke in &stats.keystrokes[*seen_keystrokes..] {
+        if !keystroke.correct
+            && let Some((row, column, _)) = key_position(*layout, &keystroke.grapheme)
+        {
+            for (mut key_cap, _) in &mut key_caps {
+                if key_cap.row == row && key_cap.column == column {
+                    key_cap.flash_until = now + FLASH_DURATION;
+                }
+            }
+        }
+    }
+    *seen_keystrokes = stats.keystrokes.len();
+
+    // Without a target every key that continues a candidate is highlighted, or that starts a
+    // word if nothing is typed. Nothing is highlighted while an error has to be removed
+    let any_in_progress = to_types.iter().any(ToType::in_progress);
+    let next_keys: Vec<_> = match target.entity {
+        Some(entity) => to_types.get(entity).into_iter().collect(),
+        None => to_types
+            .iter()
+            .filter(|to_type| !any_in_progress || to_type.in_progress())
+            .collect(),
+    }
+    .into_iter()
+    .filter(|to_type| to_type.error.is_none())
+    .filter_map(|to_type| to_type.next_grapheme())
+    .filter_map(|grapheme| key_position(*layout, grapheme))
+    .collect();
+
+    for (key_cap, mut background_color) in &mut key_caps {
+        let color = if key_cap.flash_until > now {
+            Color::Srgba(RED)
+        } else {
+            next_keys
+                .iter()
+                .filter(|&&(row, column, _)| key_cap.row == row && key_cap.column == column)
+                // Green wins if the key starts words with and without shift
+                .map(|&(_, _, shift)| shift)
+                .min()
+                .map_or(KEY_COLOR, |shift| {
+                    Color::Srgba(if shift { YELLOW } else { GREEN })
+                })
+        };
+        // Only touch the color when it changes, so the UI isn't updated every frame
+        background_color.set_if_neq(BackgroundColor(color));
+    }
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct ToggleKeyboardOverlay;
+
+fn toggle_keyboard_overlay(
+    _trigger: Trigger<ToggleKeyboardOverlay>,
+    mut overlay: ResMut<KeyboardOverlay>,
+) {
+    overlay.visible = !overlay.visible;
+}
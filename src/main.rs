@@ -12,40 +12,56 @@
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
+use abilities::AbilityPlugin;
 use asset_loader::AssetLoaderPlugin;
 use bevy::{asset::AssetMetaCheck, prelude::*};
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use camera::CameraPlugin;
 use directors::DirectorPlugin;
 use enemy::EnemyPlugin;
+use keyboard::KeyboardPlugin;
+use keyboard_overlay::KeyboardOverlayPlugin;
 use map::MapPlugin;
 use menus::MenuPlugin;
 use path::PathPlugin;
 use physics::PhysicsPlugin;
 use projectile::ProjectilePlugin;
+use replay::ReplayPlugin;
+use rng::RngPlugin;
 use sound::SoundPlugin;
 use states::StatePlugin;
+use stats::StatsPlugin;
 use tower::TowerPlugin;
+use training::TrainingPlugin;
 use typing::TypingPlugin;
 use upgrades::UpgradePlugin;
 use wasm::WasmPlugin;
+use wordlists::WordlistPlugin;
 
+mod abilities;
 mod asset_loader;
 mod camera;
 mod directors;
 mod enemy;
 mod fps;
+mod keyboard;
+mod keyboard_overlay;
 mod map;
 mod menus;
 mod path;
 mod physics;
 mod projectile;
+mod replay;
+mod rng;
 mod sound;
 mod states;
+mod stats;
 mod tower;
+mod training;
 mod typing;
 mod upgrades;
 mod wasm;
+mod wordlists;
 
 fn main() {
     App::new()
@@ -73,6 +89,17 @@ fn main() {
             UpgradePlugin,
             StatePlugin,
             SoundPlugin,
+            AbilityPlugin,
+        ))
+        // typing plugins, separate as tuples can hold at most 15 plugins
+        .add_plugins((
+            StatsPlugin,
+            WordlistPlugin,
+            KeyboardPlugin,
+            KeyboardOverlayPlugin,
+            ReplayPlugin,
+            TrainingPlugin,
+            RngPlugin,
         ))
         .run();
 }
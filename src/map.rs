@@ -1,7 +1,7 @@
 use crate::{
     asset_loader::Handles,
     physics::Position,
-    states::MenuState,
+    states::{GameSetupSystemSet, MenuState},
     tower::{Tower, TowerPriority, TowerType},
     upgrades::ArrowTowerUpgrades,
 };
@@ -14,7 +14,10 @@ impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Tile>()
             .register_type::<TileType>()
-            .add_systems(OnExit(MenuState::MainMenu), setup_map);
+            .add_systems(
+                OnExit(MenuState::MainMenu),
+                setup_map.in_set(GameSetupSystemSet),
+            );
     }
 }
 
@@ -3,9 +3,12 @@ use bevy::{color::palettes::css::DARK_GRAY, prelude::*, sprite::Anchor};
 use crate::{
     asset_loader::Handles,
     enemy::{Health, Life, Money},
-    states::{ChangeMenuState, GameState, MenuState, PauseMenuSystemSet, RunGame},
+    states::{
+        ChangeMenuState, GameSetupSystemSet, GameState, MenuState, PauseMenuSystemSet, RunGame,
+    },
     tower::Tower,
-    typing::{Action, AddToType, handle_action},
+    typing::{Action, AddToType, Combo, TypedInput, WordSource, handle_action},
+    wordlists::{Languages, WordDifficulty},
 };
 
 pub struct MenuPlugin;
@@ -16,7 +19,7 @@ impl Plugin for MenuPlugin {
             .add_systems(OnEnter(MenuState::MainMenu), spawn_main_menu)
             .add_systems(
                 OnExit(MenuState::MainMenu),
-                (spawn_money_text, spawn_life_display),
+                (spawn_money_text, spawn_life_display, spawn_combo_text).in_set(GameSetupSystemSet),
             )
             .add_systems(
                 OnEnter(MenuState::TowerSelectionMenu),
@@ -30,6 +33,7 @@ impl Plugin for MenuPlugin {
                     add_menu_button_to_type.in_set(PauseMenuSystemSet),
                     update_money_text.run_if(resource_changed::<Money>),
                     update_life_text.run_if(resource_changed::<Life>),
+                    update_combo_text.run_if(resource_changed::<Combo>),
                 ),
             )
             .add_observer(spawn_menu);
@@ -48,6 +52,10 @@ pub struct MoneyText;
 #[reflect(Component)]
 pub struct LifeText;
 
+#[derive(Component, Debug, Clone, Reflect, Default)]
+#[reflect(Component)]
+pub struct ComboText;
+
 #[derive(Component, Debug, Clone, Reflect)]
 #[reflect(Component)]
 #[repr(transparent)]
@@ -97,7 +105,8 @@ fn update_money_text(
     money: Res<Money>,
 ) {
     if let Ok(money_text) = money_text.get_single() {
-        *writer.text(money_text, 0) = format!("{}$", money.value);
+        // The combo multiplier can lead to fractional income
+        *writer.text(money_text, 0) = format!("{:.0}$", money.value.floor());
     }
 }
 
@@ -122,20 +131,71 @@ fn spawn_money_text(mut commands: Commands, handles: Res<Handles>) {
     ));
 }
 
-fn spawn_main_menu(mut commands: Commands) {
+fn update_combo_text(
+    combo_text: Query<Entity, With<ComboText>>,
+    mut writer: TextUiWriter,
+    combo: Res<Combo>,
+) {
+    if let Ok(combo_text) = combo_text.get_single() {
+        *writer.text(combo_text, 0) =
+            format!("{}x Combo ({:.1}x)", combo.value, combo.multiplier());
+    }
+}
+
+fn spawn_combo_text(mut commands: Commands, handles: Res<Handles>) {
+    commands.spawn((
+        Name::new("Combo display"),
+        Text2d::new(String::new()),
+        TextFont {
+            font: handles.font.clone(),
+            font_size: 40.0,
+            ..default()
+        },
+        TextColor::BLACK,
+        Anchor::TopCenter,
+        // At the top of the map, in front of the words
+        Transform::from_xyz(0., 280., 10.),
+        ComboText,
+    ));
+}
+
+fn spawn_main_menu(
+    mut commands: Commands,
+    menus: Query<&StateScoped<MenuState>, (With<Menu>, With<Node>)>,
+) {
+    // Going back to the main menu already spawned it
+    if menus.iter().any(|scope| scope.0 == MenuState::MainMenu) {
+        return;
+    }
     commands.trigger(SpawnMenu(MenuState::MainMenu));
 }
 
 fn toggle_pause_menu(
-    input: Res<ButtonInput<KeyCode>>,
+    mut inputs: EventReader<TypedInput>,
     mut commands: Commands,
     game_state: Res<State<GameState>>,
+    menu_state: Option<Res<State<MenuState>>>,
 ) {
-    if input.just_pressed(KeyCode::Escape) {
-        if *game_state.get() == GameState::Menu {
-            commands.trigger(RunGame);
-        } else {
-            commands.trigger(ChangeMenuState(MenuState::PauseMenu));
+    // Goes through TypedInput, so pausing is recorded as well
+    if inputs
+        .read()
+        .filter(|input| matches!(input, TypedInput::Pause))
+        .count()
+        > 0
+    {
+        match (*game_state.get(), menu_state.map(|state| *state.get())) {
+            // Drills have no pause menu, so escape goes back to the drill selection
+            (GameState::Training, _) => {
+                commands.trigger(ChangeMenuState(MenuState::TrainingMenu));
+            }
+            // There is no run to resume from the drill selection
+            (GameState::Menu, Some(MenuState::TrainingMenu)) => {
+                commands.trigger(ChangeMenuState(MenuState::MainMenu));
+            }
+            (GameState::Menu, _) => commands.trigger(RunGame),
+            (GameState::Running | GameState::Loading, _) => {
+                commands.trigger(ChangeMenuState(MenuState::PauseMenu));
+            }
         }
     }
 }
@@ -156,7 +216,7 @@ fn spawn_menu_image(mut commands: Commands, handles: Res<Handles>) {
 #[derive(Debug, Clone, Event)]
 pub struct SpawnMenu(pub MenuState);
 
-pub fn spawn_menu(trigger: Trigger<SpawnMenu>, mut commands: Commands) {
+pub fn spawn_menu(trigger: Trigger<SpawnMenu>, mut commands: Commands, languages: Res<Languages>) {
     let menu = trigger.event().0;
     commands
         .spawn((
@@ -179,7 +239,7 @@ pub fn spawn_menu(trigger: Trigger<SpawnMenu>, mut commands: Commands) {
                 TextFont::from_font_size(80.),
                 TextColor(Color::Srgba(DARK_GRAY)),
             ));
-            for button_action in menu.get_buttons() {
+            for button_action in menu.get_buttons(&languages) {
                 parent.spawn((
                     Button,
                     Node {
@@ -201,7 +261,7 @@ fn add_tower_selection_to_types(towers: Query<Entity, With<Tower>>, mut commands
         commands.trigger_targets(
             AddToType(
                 Action::ChangeMenuState(MenuState::SelectedTower(tower)),
-                None,
+                WordSource::Random(WordDifficulty::Easy),
             ),
             tower,
         );
@@ -216,7 +276,7 @@ fn add_menu_button_to_type(
         commands.trigger_targets(
             AddToType(
                 menu_button.action.clone(),
-                Some(format!("{}", menu_button.action)),
+                WordSource::Fixed(format!("{}", menu_button.action)),
             ),
             entity,
         );
@@ -237,7 +297,7 @@ pub fn button_interactions(
             Interaction::Pressed => {
                 *background_color = PRESSED_COLOR.into();
 
-                handle_action(menu_button.action.clone(), &mut commands);
+                handle_action(menu_button.action.clone(), 1., &mut commands);
             }
             Interaction::Hovered => *background_color = HOVERED_COLOR.into(),
             Interaction::None => *background_color = NORMAL_COLOR.into(),
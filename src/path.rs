@@ -4,7 +4,7 @@ use crate::{
     map::{to_rgba_index, to_world},
     physics::{apply_velocity, Position, Velocity},
     projectile::Speed,
-    states::{GameSystemSet, MenuState},
+    states::{GameSetupSystemSet, GameSystemSet, MenuState},
 };
 use bevy::prelude::*;
 use strum::{EnumIter, IntoEnumIterator};
@@ -19,7 +19,10 @@ impl Plugin for PathPlugin {
                 Update,
                 (follow_path.after(apply_velocity)).in_set(GameSystemSet),
             )
-            .add_systems(OnExit(MenuState::MainMenu), load_path);
+            .add_systems(
+                OnExit(MenuState::MainMenu),
+                load_path.in_set(GameSetupSystemSet),
+            );
     }
 }
 
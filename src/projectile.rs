@@ -4,6 +4,7 @@ use crate::{
     path::PathState,
     physics::{Layer, Obb, Position, Rotation, Velocity, apply_velocity},
     tower::{Tower, TowerPriority},
+    typing::Combo,
     upgrades::{ArrowTowerUpgrade, ArrowTowerUpgrades},
 };
 use bevy::prelude::*;
@@ -47,8 +48,9 @@ impl Tracking {
     }
 }
 
+/// Spawns arrows from the targeted tower, their damage gets multiplied by the contained reward
 #[derive(Debug, Clone, Event)]
-pub struct SpawnArrow;
+pub struct SpawnArrow(pub f64);
 
 // Arrow Tower
 /// Spawns an Arrow at the specified position, pointing towards the nearest Enemy
@@ -59,6 +61,7 @@ pub fn spawn_arrow(
     enemies: Query<(&Position, &PathState), With<Enemy>>,
     mut commands: Commands,
     handles: Res<Handles>,
+    combo: Res<Combo>,
 ) {
     let (position, upgrades, tower) = tower_query
         .get(trigger.entity())
@@ -109,7 +112,7 @@ pub fn spawn_arrow(
             Obb::new(Vec2::new(45., 10.)),
             Velocity::new((final_rotation * Vec3::X).truncate() * PROJECTILE_SPEED),
             Layer::new(1.),
-            Attack::new(1.),
+            Attack::new(combo.multiplier() * trigger.event().0),
             Health::new(
                 // Piercing value, or 1
                 (upgrades[ArrowTowerUpgrade::Piercing] + 1).into(),
@@ -0,0 +1,462 @@
+use std::{collections::VecDeque, fmt::Display};
+#[cfg(not(target_family = "wasm"))]
+use std::{env, fs, path::PathBuf};
+
+use bevy::{ecs::system::SystemParam, prelude::*, sprite::Anchor};
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_family = "wasm"))]
+use crate::asset_loader::data_dir;
+use crate::{
+    asset_loader::Handles,
+    directors::{Difficulty, DirectorMode, apply_difficulty_rules},
+    enemy::Died,
+    keyboard::KeyboardLayout,
+    rng::{GameRng, reseed_rng},
+    states::{GameSetupSystemSet, MenuState},
+    typing::{
+        MatchMode, MistypePolicy, ToType, TypedInput, TypoTolerance, collect_input,
+        handle_to_types, read_input,
+    },
+    wordlists::{Language, Languages},
+};
+
+pub struct ReplayPlugin;
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayMode>()
+            .register_type::<ReplayMode>()
+            .add_systems(
+                OnExit(MenuState::MainMenu),
+                (
+                    start_playback
+                        .after(reseed_rng)
+                        .before(apply_difficulty_rules),
+                    start_recording
+                        .after(start_playback)
+                        .after(apply_difficulty_rules),
+                )
+                    .in_set(GameSetupSystemSet),
+            )
+            .add_systems(
+                Update,
+                (
+                    play_recording
+                        .after(collect_input)
+                        .before(read_input)
+                        .run_if(replaying),
+                    record_inputs.after(play_recording).before(read_input),
+                    record_completions.after(read_input).before(handle_to_types),
+                    update_ghost_text.run_if(resource_exists::<Playback>),
+                )
+                    // Only runs get recorded, not the main menu or practice drills
+                    .run_if(resource_exists::<Recording>),
+            )
+            .add_systems(Update, pause_replay.run_if(replaying))
+            .add_systems(Last, save_on_exit)
+            .add_observer(save_on_death)
+            .add_observer(change_replay_mode);
+    }
+}
+
+/// What the run does with the last recording
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect, Default)]
+#[reflect(Resource)]
+pub enum ReplayMode {
+    /// Record the run, without using the last recording
+    #[default]
+    Record,
+    /// Play the inputs of the last recording instead of the players inputs
+    Replay,
+    /// Record the run, and show the progress of the last recording next to it
+    Ghost,
+}
+impl Display for ReplayMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Self::Record => "Record",
+            Self::Replay => "Watch Replay",
+            Self::Ghost => "Race Ghost",
+        })
+    }
+}
+
+/// Run condition, true while the inputs come from a recording
+pub fn replaying(mode: Res<ReplayMode>) -> bool {
+    *mode == ReplayMode::Replay
+}
+
+/// Something that happened at the contained time, relative to the start of the run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Timed<T> {
+    time: f64,
+    value: T,
+}
+
+/// The inputs and words of a run, saved to a file so the run can be replayed or raced as a ghost
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Recording {
+    /// The seed of the run, so the same enemies get spawned again
+    seed: u64,
+    /// The settings of the run, so it is played with the same rules again
+    #[serde(default)]
+    settings: RunSettings,
+    inputs: Vec<Timed<TypedInput>>,
+    /// The picked words and phrases, in the order they were picked
+    #[serde(default)]
+    picked_words: Vec<String>,
+    /// When `ToTypes` were completed
+    completed: Vec<f64>,
+    /// Elapsed seconds when the run started
+    #[serde(skip)]
+    start: f64,
+}
+impl Recording {
+    /// Records a word or phrase picked by the `WordPicker`
+    pub fn record_word(&mut self, word: &str) {
+        self.picked_words.push(String::from(word));
+    }
+}
+
+/// The settings that change how a run plays
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct RunSettings {
+    /// The id of the language
+    language: String,
+    difficulty: Difficulty,
+    director_mode: DirectorMode,
+    mistype_policy: MistypePolicy,
+    match_mode: MatchMode,
+    typo_tolerance: TypoTolerance,
+    keyboard_layout: KeyboardLayout,
+}
+
+/// The resources that hold the `RunSettings`
+#[derive(SystemParam)]
+struct Settings<'w> {
+    language: ResMut<'w, Language>,
+    languages: Res<'w, Languages>,
+    difficulty: ResMut<'w, Difficulty>,
+    director_mode: ResMut<'w, DirectorMode>,
+    mistype_policy: ResMut<'w, MistypePolicy>,
+    match_mode: ResMut<'w, MatchMode>,
+    typo_tolerance: ResMut<'w, TypoTolerance>,
+    keyboard_layout: ResMut<'w, KeyboardLayout>,
+}
+impl Settings<'_> {
+    fn get(&self) -> RunSettings {
+        RunSettings {
+            language: self.language.id.clone(),
+            difficulty: *self.difficulty,
+            director_mode: *self.director_mode,
+            mistype_policy: *self.mistype_policy,
+            match_mode: *self.match_mode,
+            typo_tolerance: *self.typo_tolerance,
+            keyboard_layout: *self.keyboard_layout,
+        }
+    }
+    fn set(&mut self, settings: &RunSettings) {
+        match self
+            .languages
+            .value
+            .iter()
+            .find(|language| language.id == settings.language)
+        {
+            Some(language) => *self.language = language.clone(),
+            None => warn!(
+                "The language {} of the recording is missing",
+                settings.language
+            ),
+        }
+        *self.difficulty = settings.difficulty;
+        *self.director_mode = settings.director_mode;
+        *self.mistype_policy = settings.mistype_policy;
+        *self.match_mode = settings.match_mode;
+        *self.typo_tolerance = settings.typo_tolerance;
+        *self.keyboard_layout = settings.keyboard_layout;
+    }
+}
+
+/// The last recording, while it is being replayed or raced
+#[derive(Resource, Debug, Clone)]
+struct Playback {
+    recording: Recording,
+    /// The index of the next input to play
+    next_input: usize,
+}
+
+/// The words of the replayed recording that weren't picked yet.
+/// The `WordPicker` hands them out instead of picking new words, as the picks depend on the
+/// timing of the inputs, which replays only reproduce to the frame
+#[derive(Resource, Debug, Clone, Default)]
+#[repr(transparent)]
+pub struct ReplayedWords {
+    value: VecDeque<String>,
+}
+impl ReplayedWords {
+    pub fn next(&mut self) -> Option<String> {
+        self.value.pop_front()
+    }
+}
+
+#[derive(Component, Debug, Clone, Reflect, Default)]
+#[reflect(Component)]
+struct GhostText;
+
+#[derive(Component, Debug, Clone, Reflect, Default)]
+#[reflect(Component)]
+struct ReplayPausedText;
+
+/// Loads the last recording, and restarts the random number generation and the settings from it
+fn start_playback(
+    mut commands: Commands,
+    mut mode: ResMut<ReplayMode>,
+    mut rng: ResMut<GameRng>,
+    mut settings: Settings,
+    handles: Res<Handles>,
+) {
+    commands.remove_resource::<Playback>();
+    commands.remove_resource::<ReplayedWords>();
+    if *mode == ReplayMode::Record {
+        return;
+    }
+    let Some(recording) = load_recording() else {
+        *mode = ReplayMode::Record;
+        return;
+    };
+
+    rng.reseed(recording.seed);
+    settings.set(&recording.settings);
+    if *mode == ReplayMode::Replay {
+        commands.insert_resource(ReplayedWords {
+            value: recording.picked_words.iter().cloned().collect(),
+        });
+    }
+    commands.insert_resource(Playback {
+        recording,
+        next_input: 0,
+    });
+
+    if *mode == ReplayMode::Ghost {
+        commands.spawn((
+            Name::new("Ghost display"),
+            Text2d::new(String::new()),
+            TextFont {
+                font: handles.font.clone(),
+                font_size: 40.0,
+                ..default()
+            },
+            TextColor::BLACK,
+            Anchor::TopCenter,
+            Transform::from_xyz(0., -50., 0.),
+            GhostText,
+        ));
+    }
+}
+
+fn start_recording(mut commands: Commands, rng: Res<GameRng>, settings: Settings, time: Res<Time>) {
+    commands.insert_resource(Recording {
+        seed: rng.run_seed(),
+        settings: settings.get(),
+        start: time.elapsed_secs_f64(),
+        ..default()
+    });
+}
+
+/// Sends the recorded inputs once their time has come
+fn play_recording(
+    mut playback: ResMut<Playback>,
+    mut inputs: EventWriter<TypedInput>,
+    recording: Res<Recording>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs_f64() - recording.start;
+
+    while let Some(input) = playback.recording.inputs.get(playback.next_input)
+        && input.time <= now
+    {
+        inputs.send(input.value.clone());
+        playback.next_input += 1;
+    }
+}
+
+fn record_inputs(
+    mut inputs: EventReader<TypedInput>,
+    mut recording: ResMut<Recording>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs_f64() - recording.start;
+    for input in inputs.read() {
+        recording.inputs.push(Timed {
+            time: now,
+            value: input.clone(),
+        });
+    }
+}
+
+fn record_completions(
+    to_types: Query<&ToType, Changed<ToType>>,
+    mut recording: ResMut<Recording>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs_f64() - recording.start;
+    for to_type in &to_types {
+        if to_type.is_complete() {
+            recording.completed.push(now);
+        }
+    }
+}
+
+/// Shows how many words the ghost and the player completed so far
+fn update_ghost_text(
+    ghost_text: Query<Entity, With<GhostText>>,
+    mut writer: TextUiWriter,
+    playback: Res<Playback>,
+    recording: Res<Recording>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs_f64() - recording.start;
+    if let Ok(ghost_text) = ghost_text.get_single() {
+        let ghost = playback
+            .recording
+            .completed
+            .iter()
+            .take_while(|&&time| time <= now)
+            .count();
+        *writer.text(ghost_text, 0) = format!(
+            "Ghost: {ghost} words\nYou: {} words",
+            recording.completed.len()
+        );
+    }
+}
+
+/// Pauses and resumes a replay with escape.
+/// The pause menu can't be used while replaying, as the recording types into it
+fn pause_replay(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut time: ResMut<Time<Virtual>>,
+    paused_texts: Query<Entity, With<ReplayPausedText>>,
+    mut commands: Commands,
+    handles: Res<Handles>,
+) {
+    if !keys.just_pressed(KeyCode::Escape) {
+        return;
+    }
+
+    if time.is_paused() {
+        time.unpause();
+        for paused_text in &paused_texts {
+            commands.entity(paused_text).despawn_recursive();
+        }
+    } else {
+        time.pause();
+        commands.spawn((
+            Name::new("Replay paused display"),
+            Text2d::new("Paused"),
+            TextFont {
+                font: handles.font.clone(),
+                font_size: 80.0,
+                ..default()
+            },
+            TextColor::BLACK,
+            Transform::from_xyz(0., 0., 101.),
+            ReplayPausedText,
+        ));
+    }
+}
+
+/// Saves the recording once the run is lost, and stops recording
+fn save_on_death(
+    _trigger: Trigger<Died>,
+    mut commands: Commands,
+    recording: Option<Res<Recording>>,
+    mode: Res<ReplayMode>,
+) {
+    // Replays would only save the same recording again
+    if let Some(recording) = recording
+        && *mode != ReplayMode::Replay
+    {
+        save_recording(&recording);
+    }
+    commands.remove_resource::<Recording>();
+    commands.remove_resource::<Playback>();
+    commands.remove_resource::<ReplayedWords>();
+}
+
+fn save_on_exit(
+    mut exits: EventReader<AppExit>,
+    recording: Option<Res<Recording>>,
+    mode: Res<ReplayMode>,
+) {
+    if exits.read().next().is_some()
+        && let Some(recording) = recording
+        && *mode != ReplayMode::Replay
+    {
+        save_recording(&recording);
+    }
+}
+
+/// The file the last run is recorded to.
+/// Can be set with `TYPINGTD_RECORDING`, defaults to the platforms data directory
+#[cfg(not(target_family = "wasm"))]
+fn recording_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("TYPINGTD_RECORDING") {
+        return Some(PathBuf::from(path));
+    }
+
+    data_dir().map(|data_dir| data_dir.join("last_run.json"))
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn load_recording() -> Option<Recording> {
+    let path = recording_path()?;
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(error) => {
+            warn!("Couldn't read recording {}: {error}", path.display());
+            return None;
+        }
+    };
+    serde_json::from_str(&text)
+        .inspect_err(|error| warn!("Couldn't parse recording {}: {error}", path.display()))
+        .ok()
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_recording(recording: &Recording) {
+    // Don't replace the last run with one where nothing happened
+    if recording.inputs.is_empty() {
+        return;
+    }
+    let Some(path) = recording_path() else {
+        return;
+    };
+
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| {
+            fs::write(
+                &path,
+                serde_json::to_string(recording).expect("Recordings should be serializable"),
+            )
+        });
+    if let Err(error) = result {
+        warn!("Couldn't save recording {}: {error}", path.display());
+    }
+}
+
+// The web build has no file system to store recordings in
+#[cfg(target_family = "wasm")]
+const fn load_recording() -> Option<Recording> {
+    None
+}
+#[cfg(target_family = "wasm")]
+const fn save_recording(_recording: &Recording) {}
+
+#[derive(Debug, Clone, Event)]
+pub struct ChangeReplayMode(pub ReplayMode);
+
+fn change_replay_mode(trigger: Trigger<ChangeReplayMode>, mut mode: ResMut<ReplayMode>) {
+    *mode = trigger.event().0;
+}
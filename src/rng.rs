@@ -0,0 +1,86 @@
+use std::env;
+
+use bevy::prelude::*;
+use rand::{RngCore, SeedableRng, random};
+use rand_chacha::ChaCha8Rng;
+use strum::{EnumCount, EnumIter, IntoEnumIterator};
+
+use crate::states::{GameSetupSystemSet, MenuState};
+
+pub struct RngPlugin;
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameRng>()
+            .register_type::<GameRng>()
+            .add_systems(
+                OnExit(MenuState::MainMenu),
+                reseed_rng.in_set(GameSetupSystemSet),
+            );
+    }
+}
+
+/// The parts of the game that draw random numbers, each from its own stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumCount, EnumIter)]
+pub enum RngStream {
+    /// When the director tries to spawn enemies
+    Director,
+    /// Which enemies get spawned
+    Enemies,
+    /// Which words and phrases get picked
+    Words,
+}
+
+/// The random number generators of a run, all derived from one seed.
+/// Every subsystem has its own stream, so one of them drawing more numbers (e.g. more words on a
+/// faster machine) doesn't change what the others draw
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct GameRng {
+    /// The seed of every run, a random one is used if None.
+    /// Can be set with `TYPINGTD_SEED`
+    pub seed: Option<u64>,
+    /// The seed of the current run
+    run_seed: u64,
+    #[reflect(ignore)]
+    streams: [ChaCha8Rng; RngStream::COUNT],
+}
+impl Default for GameRng {
+    fn default() -> Self {
+        Self {
+            seed: seed_from_env(),
+            run_seed: 0,
+            streams: std::array::from_fn(|_| ChaCha8Rng::from_entropy()),
+        }
+    }
+}
+impl GameRng {
+    pub const fn run_seed(&self) -> u64 {
+        self.run_seed
+    }
+    pub fn stream(&mut self, stream: RngStream) -> &mut ChaCha8Rng {
+        &mut self.streams[stream as usize]
+    }
+    /// Restarts every stream from the seed
+    pub fn reseed(&mut self, seed: u64) {
+        let mut master = ChaCha8Rng::seed_from_u64(seed);
+        self.run_seed = seed;
+        for stream in RngStream::iter() {
+            self.streams[stream as usize] = ChaCha8Rng::seed_from_u64(master.next_u64());
+        }
+    }
+}
+
+/// Reads the seed of every run from `TYPINGTD_SEED`
+fn seed_from_env() -> Option<u64> {
+    let seed = env::var("TYPINGTD_SEED").ok()?;
+    seed.trim()
+        .parse()
+        .inspect_err(|error| warn!("TYPINGTD_SEED {seed} is no valid seed: {error}"))
+        .ok()
+}
+
+/// Restarts the random number generation for a new run
+pub fn reseed_rng(mut rng: ResMut<GameRng>) {
+    let seed = rng.seed.unwrap_or_else(random);
+    rng.reseed(seed);
+}
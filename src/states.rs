@@ -4,10 +4,15 @@ use bevy::prelude::*;
 use strum::IntoEnumIterator;
 
 use crate::{
+    directors::{Difficulty, DirectorMode},
+    keyboard::KeyboardLayout,
     menus::SpawnMenu,
+    replay::ReplayMode,
     tower::TowerPriority,
-    typing::{Action, Language},
+    training::Drill,
+    typing::{Action, MatchMode, MistypePolicy, TypoTolerance},
     upgrades::ArrowTowerUpgrade,
+    wordlists::Languages,
 };
 
 pub struct StatePlugin;
@@ -17,6 +22,15 @@ impl Plugin for StatePlugin {
             .add_sub_state::<MenuState>()
             .configure_sets(Update, GameSystemSet.run_if(in_state(GameState::Running)))
             .configure_sets(Update, PauseMenuSystemSet.run_if(in_state(GameState::Menu)))
+            .configure_sets(
+                Update,
+                TrainingSystemSet.run_if(in_state(GameState::Training)),
+            )
+            // The main menu is also left to open the practice menu
+            .configure_sets(
+                OnExit(MenuState::MainMenu),
+                GameSetupSystemSet.run_if(in_state(GameState::Running)),
+            )
             .enable_state_scoped_entities::<GameState>()
             .enable_state_scoped_entities::<MenuState>()
             .add_observer(run_game)
@@ -30,6 +44,13 @@ pub struct GameSystemSet;
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PauseMenuSystemSet;
 
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TrainingSystemSet;
+
+/// Systems that set up the map and HUD when a run starts
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameSetupSystemSet;
+
 #[derive(SubStates, Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
 #[source(GameState = GameState::Menu)]
 pub enum MenuState {
@@ -37,20 +58,68 @@ pub enum MenuState {
     MainMenu,
     PauseMenu,
     LanguageMenu,
+    MistypeMenu,
+    MatchMenu,
+    AssistMenu,
+    KeyboardMenu,
+    DirectorMenu,
+    TrainingMenu,
     TowerSelectionMenu,
     SelectedTower(Entity),
     TowerUpgradeMenu(Entity),
     TowerPriorityMenu(Entity),
 }
 impl MenuState {
-    pub fn get_buttons(&self) -> Vec<Action> {
+    pub fn get_buttons(&self, languages: &Languages) -> Vec<Action> {
         match *self {
-            Self::MainMenu => vec![Action::RunGame],
-            Self::PauseMenu => [Self::LanguageMenu, Self::TowerSelectionMenu]
+            Self::MainMenu => {
+                let mut buttons: Vec<Action> = Difficulty::iter().map(Action::StartRun).collect();
+                buttons.push(Action::ChangeMenuState(Self::TrainingMenu));
+                // The web build has no file system to load recordings from
+                if cfg!(not(target_family = "wasm")) {
+                    buttons
+                        .extend([ReplayMode::Replay, ReplayMode::Ghost].map(Action::StartReplay));
+                }
+                buttons
+            }
+            Self::PauseMenu => [
+                Self::LanguageMenu,
+                Self::MistypeMenu,
+                Self::MatchMenu,
+                Self::AssistMenu,
+                Self::KeyboardMenu,
+                Self::DirectorMenu,
+                Self::TowerSelectionMenu,
+            ]
+            .into_iter()
+            .map(Action::ChangeMenuState)
+            .collect(),
+            Self::LanguageMenu => languages
+                .value
+                .iter()
+                .cloned()
+                .map(Action::ChangeLanguage)
+                .collect(),
+            Self::MistypeMenu => MistypePolicy::iter()
+                .map(Action::ChangeMistypePolicy)
+                .collect(),
+            Self::MatchMenu => MatchMode::iter().map(Action::ChangeMatchMode).collect(),
+            Self::AssistMenu => TypoTolerance::OPTIONS
+                .into_iter()
+                .map(Action::ChangeTypoTolerance)
+                .collect(),
+            Self::KeyboardMenu => KeyboardLayout::iter()
+                .map(Action::ChangeKeyboardLayout)
+                .chain([Action::ToggleKeyboardOverlay])
+                .collect(),
+            Self::DirectorMenu => DirectorMode::iter()
+                .map(Action::ChangeDirectorMode)
+                .collect(),
+            Self::TrainingMenu => Drill::ALL
                 .into_iter()
-                .map(Action::ChangeMenuState)
+                .map(Action::StartDrill)
+                .chain([Action::ChangeMenuState(Self::MainMenu)])
                 .collect(),
-            Self::LanguageMenu => Language::iter().map(Action::ChangeLanguage).collect(),
             Self::TowerSelectionMenu => Vec::new(),
             Self::SelectedTower(entity) => [
                 Self::TowerUpgradeMenu(entity),
@@ -74,6 +143,12 @@ impl Display for MenuState {
             Self::MainMenu => "Main Menu",
             Self::PauseMenu => "Options",
             Self::LanguageMenu => "Languages",
+            Self::MistypeMenu => "Mistakes",
+            Self::MatchMenu => "Compare",
+            Self::AssistMenu => "Assist",
+            Self::KeyboardMenu => "Keyboard",
+            Self::DirectorMenu => "Director",
+            Self::TrainingMenu => "Practice",
             Self::TowerSelectionMenu => "Select Tower",
             Self::SelectedTower(_) => "Select Option",
             Self::TowerUpgradeMenu(_) => "Upgrades",
@@ -88,6 +163,8 @@ pub enum GameState {
     Loading,
     Running,
     Menu,
+    /// Typing drills, without towers or enemies
+    Training,
 }
 
 #[derive(Debug, Clone, Event)]
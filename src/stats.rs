@@ -0,0 +1,270 @@
+use std::{f64::consts::LN_2, fmt::Write};
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    asset_loader::Handles,
+    rng::GameRng,
+    states::{GameState, MenuState},
+    typing::Action,
+};
+
+/// The amount of graphemes that count as one word for wpm calculations
+const CHARS_PER_WORD: f64 = 5.;
+
+pub struct StatsPlugin;
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TypingStats>()
+            .register_type::<TypingStats>()
+            .register_type::<StatsDisplay>()
+            .add_systems(OnExit(MenuState::MainMenu), reset_stats)
+            .add_systems(
+                Update,
+                count_typing_time
+                    .run_if(in_state(GameState::Running).or(in_state(GameState::Training))),
+            )
+            .add_observer(spawn_stats_display);
+    }
+}
+
+/// A single key press, as seen by `read_input`
+#[derive(Debug, Clone, Reflect)]
+pub struct Keystroke {
+    pub grapheme: String,
+    /// The grapheme the targeted `ToType` expected, if there was one
+    pub expected: Option<String>,
+    pub correct: bool,
+    pub to_type: Option<Entity>,
+    pub action: Option<Action>,
+    /// Elapsed seconds at the time of the key press
+    pub time: f64,
+}
+
+/// How a grapheme was typed when it was expected
+#[derive(Debug, Clone, Reflect, Default)]
+struct GraphemeStats {
+    attempts: u32,
+    misses: u32,
+    /// Total seconds since the previous keystroke of the same word
+    latency: f64,
+    /// The amount of keystrokes in `latency`
+    timed: u32,
+}
+
+/// Keystroke counts that fade out over time, so recent typing counts the most
+#[derive(Debug, Clone, Reflect, Default)]
+struct RecentKeystrokes {
+    correct: f64,
+    total: f64,
+    /// Seconds of typing at the last keystroke
+    time: f64,
+}
+impl RecentKeystrokes {
+    /// Seconds until a keystroke only counts half
+    const HALF_LIFE: f64 = 15.;
+
+    /// How much the counts have faded since the last keystroke
+    fn decay(&self, now: f64) -> f64 {
+        0.5_f64.powf((now - self.time).max(0.) / Self::HALF_LIFE)
+    }
+    fn add(&mut self, correct: bool, time: f64) {
+        let decay = self.decay(time);
+        self.correct = self.correct * decay + f64::from(u8::from(correct));
+        self.total = self.total * decay + 1.;
+        self.time = time;
+    }
+}
+
+#[derive(Resource, Debug, Clone, Reflect, Default)]
+#[reflect(Resource)]
+pub struct TypingStats {
+    pub keystrokes: Vec<Keystroke>,
+    /// How often each grapheme was missed
+    pub misses: HashMap<String, u32>,
+    /// The keystrokes summed up by the expected grapheme, so the weaknesses don't have to look at
+    /// every keystroke
+    graphemes: HashMap<String, GraphemeStats>,
+    /// The amount of correct keystrokes
+    correct: usize,
+    recent: RecentKeystrokes,
+    /// Seconds since the first keystroke, without the time spent in menus
+    typing_time: f64,
+}
+impl TypingStats {
+    pub fn record(&mut self, keystroke: Keystroke) {
+        if let Some(ref expected) = keystroke.expected {
+            let grapheme = self.graphemes.entry(expected.clone()).or_default();
+            grapheme.attempts += 1;
+            if !keystroke.correct {
+                grapheme.misses += 1;
+            }
+            // Latency only makes sense between correct keystrokes of the same word
+            if keystroke.correct
+                && let Some(previous) = self.keystrokes.last()
+                && previous.correct
+                && previous.to_type == keystroke.to_type
+            {
+                grapheme.latency += keystroke.time - previous.time;
+                grapheme.timed += 1;
+            }
+        }
+        if !keystroke.correct {
+            *self
+                .misses
+                .entry(
+                    keystroke
+                        .expected
+                        .clone()
+                        .unwrap_or_else(|| keystroke.grapheme.clone()),
+                )
+                .or_default() += 1;
+        }
+        if keystroke.correct {
+            self.correct += 1;
+        }
+        self.recent.add(keystroke.correct, self.typing_time);
+        self.keystrokes.push(keystroke);
+    }
+    pub const fn correct_count(&self) -> usize {
+        self.correct
+    }
+    /// Minutes of typing since the first keystroke
+    fn minutes(&self) -> Option<f64> {
+        let minutes = self.typing_time / 60.;
+        (minutes > 0.).then_some(minutes)
+    }
+    /// Words per minute, only counting correct graphemes
+    pub fn wpm(&self) -> f64 {
+        self.minutes().map_or(0., |minutes| {
+            self.correct_count() as f64 / CHARS_PER_WORD / minutes
+        })
+    }
+    /// Words per minute, counting every grapheme
+    pub fn raw_wpm(&self) -> f64 {
+        self.minutes().map_or(0., |minutes| {
+            self.keystrokes.len() as f64 / CHARS_PER_WORD / minutes
+        })
+    }
+    /// The fraction of correct keystrokes, between 0 and 1
+    pub fn accuracy(&self) -> f64 {
+        if self.keystrokes.is_empty() {
+            return 1.;
+        }
+        self.correct_count() as f64 / self.keystrokes.len() as f64
+    }
+    /// Words per minute of the recent keystrokes, only counting correct graphemes
+    pub fn recent_wpm(&self) -> f64 {
+        // The seconds the faded counts cover, shorter than the full window early on
+        let window = RecentKeystrokes::HALF_LIFE / LN_2
+            * (1. - 0.5_f64.powf(self.typing_time / RecentKeystrokes::HALF_LIFE));
+        if window <= 0. {
+            return 0.;
+        }
+        self.recent.correct * self.recent.decay(self.typing_time) / CHARS_PER_WORD / (window / 60.)
+    }
+    /// The fraction of correct recent keystrokes, between 0 and 1
+    pub fn recent_accuracy(&self) -> f64 {
+        if self.recent.total == 0. {
+            return 1.;
+        }
+        self.recent.correct / self.recent.total
+    }
+    /// Returns the `amount` most missed graphemes, most missed first
+    pub fn most_missed(&self, amount: usize) -> Vec<(&str, u32)> {
+        let mut misses: Vec<_> = self
+            .misses
+            .iter()
+            .map(|(grapheme, &count)| (grapheme.as_str(), count))
+            .collect();
+        misses.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        misses.truncate(amount);
+        misses
+    }
+    /// Returns how weak the player is at each grapheme, based on how often it gets missed and how
+    /// much slower than average it gets typed. 0 means no weakness
+    pub fn weaknesses(&self) -> HashMap<String, f64> {
+        let (total_latency, total_timed) = self
+            .graphemes
+            .values()
+            .fold((0., 0), |(latency, timed), grapheme| {
+                (latency + grapheme.latency, timed + grapheme.timed)
+            });
+        let average_latency = total_latency / f64::from(total_timed.max(1));
+
+        self.graphemes
+            .iter()
+            .map(|(grapheme, stats)| {
+                let miss_rate = f64::from(stats.misses) / f64::from(stats.attempts);
+                let slowness = if stats.timed == 0 || average_latency == 0. {
+                    0.
+                } else {
+                    (stats.latency / f64::from(stats.timed) / average_latency - 1.).max(0.)
+                };
+                (grapheme.clone(), miss_rate + slowness)
+            })
+            .collect()
+    }
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{:.0} WPM ({:.0} raw)\n{:.1}% Accuracy",
+            self.wpm(),
+            self.raw_wpm(),
+            self.accuracy() * 100.
+        );
+        let most_missed = self.most_missed(5);
+        if !most_missed.is_empty() {
+            summary.push_str("\nMissed:");
+            for (grapheme, count) in most_missed {
+                write!(summary, " '{grapheme}' {count}x").expect("Writing to a String can't fail");
+            }
+        }
+        summary
+    }
+}
+
+fn reset_stats(mut stats: ResMut<TypingStats>) {
+    *stats = TypingStats::default();
+}
+
+/// Counts the seconds of typing, menus are left out so they don't lower the wpm
+fn count_typing_time(mut stats: ResMut<TypingStats>, time: Res<Time>) {
+    if !stats.keystrokes.is_empty() {
+        stats.typing_time += time.delta_secs_f64();
+    }
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct ShowStats;
+
+#[derive(Component, Debug, Clone, Reflect, Default)]
+#[reflect(Component)]
+pub struct StatsDisplay;
+
+fn spawn_stats_display(
+    _trigger: Trigger<ShowStats>,
+    mut commands: Commands,
+    stats: Res<TypingStats>,
+    handles: Res<Handles>,
+    rng: Res<GameRng>,
+    game_state: Res<State<GameState>>,
+) {
+    let mut summary = stats.summary();
+    // Drills aren't seeded, but the seed of a run is needed to play it again
+    if *game_state.get() == GameState::Running {
+        write!(summary, "\nSeed: {}", rng.run_seed()).expect("Writing to a String can't fail");
+    }
+
+    commands.spawn((
+        Name::new("Stats display"),
+        StatsDisplay,
+        Text2d::new(summary),
+        TextFont {
+            font: handles.font.clone(),
+            font_size: 40.,
+            ..default()
+        },
+        TextColor::WHITE,
+        Transform::from_xyz(0., -180., 101.),
+    ));
+}
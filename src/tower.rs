@@ -3,7 +3,8 @@ use strum::EnumIter;
 
 use crate::{
     states::GameSystemSet,
-    typing::{Action, AddToType},
+    typing::{Action, AddToType, WordSource},
+    wordlists::WordDifficulty,
 };
 
 pub struct TowerPlugin;
@@ -51,7 +52,13 @@ fn insert_tower_typing(
 ) {
     for (entity, children) in &towers {
         if children.map_or(true, |children| children.is_empty()) {
-            commands.trigger_targets(AddToType(Action::SpawnArrow(entity), None), entity);
+            commands.trigger_targets(
+                AddToType(
+                    Action::SpawnArrow(entity),
+                    WordSource::Random(WordDifficulty::Any),
+                ),
+                entity,
+            );
         }
     }
 }
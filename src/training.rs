@@ -0,0 +1,201 @@
+use std::fmt::Display;
+
+use bevy::prelude::*;
+
+use crate::{
+    asset_loader::Handles,
+    states::{ChangeMenuState, GameState, MenuState, TrainingSystemSet},
+    stats::{ShowStats, StatsDisplay, TypingStats},
+    typing::{Action, AddToType, WordSource},
+    wordlists::WordDifficulty,
+};
+
+/// The amount of words visible at once during a drill
+const DRILL_SLOTS: usize = 3;
+/// Horizontal distance between the words of a drill
+const DRILL_SLOT_SPACING: f32 = 300.;
+
+pub struct TrainingPlugin;
+impl Plugin for TrainingPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<DrillSlot>()
+            .register_type::<DrillText>()
+            .add_systems(OnEnter(GameState::Training), spawn_drill)
+            .add_systems(OnExit(MenuState::TrainingMenu), remove_drill_results)
+            .add_systems(
+                Update,
+                (insert_drill_typing, update_drill).in_set(TrainingSystemSet),
+            )
+            .add_observer(start_drill)
+            .add_observer(count_drill_word);
+    }
+}
+
+/// A typing test, without towers or enemies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum Drill {
+    /// Type as many words as possible in the given seconds
+    Timed(u32),
+    /// Type the given amount of words as fast as possible
+    Words(u32),
+}
+impl Drill {
+    /// The drills shown in the practice menu
+    pub const ALL: [Self; 4] = [
+        Self::Timed(30),
+        Self::Timed(60),
+        Self::Words(25),
+        Self::Words(50),
+    ];
+}
+impl Display for Drill {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Self::Timed(seconds) => format!("{seconds}s Test"),
+            Self::Words(words) => format!("{words} Words"),
+        })
+    }
+}
+
+/// The drill that is currently being typed
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+struct ActiveDrill {
+    drill: Drill,
+    /// Elapsed seconds at the first keystroke, the drill only starts once the player starts typing
+    started: Option<f64>,
+    /// The amount of words handed out
+    spawned: u32,
+    /// The amount of words typed
+    completed: u32,
+}
+impl ActiveDrill {
+    const fn has_words_left(&self) -> bool {
+        match self.drill {
+            Drill::Timed(_) => true,
+            Drill::Words(words) => self.spawned < words,
+        }
+    }
+}
+
+/// Holds one word of the drill at a time
+#[derive(Component, Debug, Clone, Reflect, Default)]
+#[reflect(Component)]
+struct DrillSlot;
+
+/// Shows the time or words left
+#[derive(Component, Debug, Clone, Reflect, Default)]
+#[reflect(Component)]
+struct DrillText;
+
+#[derive(Debug, Clone, Event)]
+pub struct StartDrill(pub Drill);
+
+fn start_drill(
+    trigger: Trigger<StartDrill>,
+    mut commands: Commands,
+    mut stats: ResMut<TypingStats>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    commands.insert_resource(ActiveDrill {
+        drill: trigger.event().0,
+        started: None,
+        spawned: 0,
+        completed: 0,
+    });
+    *stats = TypingStats::default();
+    next_state.set(GameState::Training);
+}
+
+/// Removes the results of the last drill, when the next one starts or the practice menu is left
+fn remove_drill_results(mut commands: Commands, stats_displays: Query<Entity, With<StatsDisplay>>) {
+    for stats_display in &stats_displays {
+        commands.entity(stats_display).despawn_recursive();
+    }
+}
+
+fn spawn_drill(mut commands: Commands, handles: Res<Handles>) {
+    for i in 0..DRILL_SLOTS {
+        let x = (i as f32 - (DRILL_SLOTS - 1) as f32 / 2.) * DRILL_SLOT_SPACING;
+        commands.spawn((
+            Name::new("Drill slot"),
+            DrillSlot,
+            Transform::from_xyz(x, 0., 0.),
+            Visibility::default(),
+            StateScoped(GameState::Training),
+        ));
+    }
+    commands.spawn((
+        Name::new("Drill display"),
+        Text2d::new(String::new()),
+        TextFont {
+            font: handles.font.clone(),
+            font_size: 80.0,
+            ..default()
+        },
+        Transform::from_xyz(0., 150., 0.),
+        DrillText,
+        StateScoped(GameState::Training),
+    ));
+}
+
+fn insert_drill_typing(
+    slots: Query<(Entity, Option<&Children>), With<DrillSlot>>,
+    mut drill: ResMut<ActiveDrill>,
+    mut commands: Commands,
+) {
+    for (entity, children) in &slots {
+        if children.map_or(true, |children| children.is_empty()) && drill.has_words_left() {
+            drill.spawned += 1;
+            commands.trigger_targets(
+                AddToType(Action::DrillWord, WordSource::Random(WordDifficulty::Any)),
+                entity,
+            );
+        }
+    }
+}
+
+/// Updates the time or words left, and shows the stats once the drill is over
+fn update_drill(
+    mut drill: ResMut<ActiveDrill>,
+    stats: Res<TypingStats>,
+    drill_text: Query<Entity, With<DrillText>>,
+    mut writer: TextUiWriter,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    if drill.started.is_none() {
+        drill.started = stats.keystrokes.first().map(|keystroke| keystroke.time);
+    }
+    let elapsed = drill
+        .started
+        .map_or(0., |started| time.elapsed_secs_f64() - started);
+
+    let (text, finished) = match drill.drill {
+        Drill::Timed(seconds) => {
+            let left = (f64::from(seconds) - elapsed).max(0.);
+            (format!("{}s", left.ceil()), left <= 0.)
+        }
+        Drill::Words(words) => (
+            format!("{}/{words}", drill.completed),
+            drill.completed >= words,
+        ),
+    };
+    if let Ok(drill_text) = drill_text.get_single() {
+        *writer.text(drill_text, 0) = text;
+    }
+
+    if finished {
+        commands.trigger(ShowStats);
+        commands.trigger(ChangeMenuState(MenuState::TrainingMenu));
+    }
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct DrillWordTyped;
+
+fn count_drill_word(_trigger: Trigger<DrillWordTyped>, drill: Option<ResMut<ActiveDrill>>) {
+    if let Some(mut drill) = drill {
+        drill.completed += 1;
+    }
+}
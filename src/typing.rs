@@ -1,81 +1,100 @@
 use std::fmt::Display;
 
 use bevy::{
-    color::palettes::css::GREEN,
-    input::{
-        ButtonState,
-        keyboard::{Key, KeyboardInput},
-    },
+    color::palettes::css::{GREEN, LIGHT_GRAY, RED},
     prelude::*,
+    sprite::Anchor,
+    text::{ComputedTextBlock, TextBounds, TextLayoutInfo},
+    window::{Ime, PrimaryWindow},
+    winit::RawWinitWindowEvent,
 };
-use bevy_device_lang::get_lang;
-use rand::{Rng, thread_rng};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use strum::EnumIter;
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+use unicode_segmentation::UnicodeSegmentation;
+use winit::{
+    event::{ElementState, WindowEvent},
+    keyboard::{Key as WinitKey, NamedKey},
+};
 
 use crate::{
+    abilities::ClearScreen,
     asset_loader::Handles,
+    directors::{ChangeDifficulty, ChangeDirectorMode, Difficulty, DirectorMode},
+    enemy::Money,
+    keyboard::{ChangeKeyboardLayout, KeyboardLayout, LayoutScoring},
+    keyboard_overlay::ToggleKeyboardOverlay,
     physics::Layer,
     projectile::SpawnArrow,
+    replay::{ChangeReplayMode, ReplayMode, replaying},
     states::{ChangeMenuState, GameState, MenuState, RunGame, change_menu_state},
+    stats::{Keystroke, TypingStats},
     tower::{ChangeTowerPriority, TowerPriority},
+    training::{Drill, DrillWordTyped, StartDrill},
     upgrades::{ArrowTowerUpgrade, UpgradeTower},
+    wordlists::{ChangeLanguage, Language, WordDifficulty, WordPicker},
 };
 
 // Plugin
 pub struct TypingPlugin;
 impl Plugin for TypingPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<Language>()
+        app.init_resource::<TypingTarget>()
+            .init_resource::<MistypePolicy>()
+            .init_resource::<MistypePolicyChosen>()
+            .init_resource::<MatchMode>()
+            .init_resource::<TypoTolerance>()
+            .init_resource::<Combo>()
+            .init_resource::<WordStyle>()
             .register_type::<ToType>()
-            .add_systems(Startup, set_device_language)
+            .register_type::<TypingTarget>()
+            .register_type::<MistypePolicy>()
+            .register_type::<MistypePolicyChosen>()
+            .register_type::<MatchMode>()
+            .register_type::<TypoTolerance>()
+            .register_type::<Combo>()
+            .register_type::<WordStyle>()
+            .register_type::<WordCaret>()
+            .register_type::<WordBackground>()
+            .register_type::<MistypeFeedback>()
+            .add_event::<TypedInput>()
+            .add_systems(Startup, enable_ime)
+            .add_systems(OnExit(MenuState::MainMenu), reset_combo)
             .add_systems(
                 Update,
                 (
-                    read_input,
+                    collect_input.run_if(not(replaying)),
+                    read_input.after(collect_input),
                     handle_text_display.after(read_input),
+                    update_word_decorations.after(handle_text_display),
+                    show_mistypes.after(read_input),
                     handle_to_types.after(read_input),
                 ),
             )
             .add_observer(add_to_type)
-            .add_observer(change_language)
+            .add_observer(change_mistype_policy)
+            .add_observer(change_match_mode)
+            .add_observer(change_typo_tolerance)
             .add_observer(change_menu_state);
     }
 }
 
-#[derive(Resource, Debug, Clone, Copy, Reflect, Default, EnumIter)]
-#[reflect(Resource)]
-// pub struct Language(Languages);
-// #[derive(Debug, Clone, Reflect, Default)]
-pub enum Language {
-    #[default]
-    English,
-    German,
-}
-
-#[derive(Default, Deserialize, Asset, Debug, TypePath)]
-pub struct Wordlists {
-    german: Vec<String>,
-    english: Vec<String>,
-}
-impl Wordlists {
-    /// Returns a random word from the inputted Language's wordlist
-    pub fn get_word(&self, language: Language) -> String {
-        match language {
-            Language::English => {
-                self.english[thread_rng().gen_range(0..self.english.len())].clone()
-            }
-            Language::German => self.german[thread_rng().gen_range(0..self.german.len())].clone(),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Reflect)]
 pub enum Action {
     SpawnArrow(Entity),
     ChangeLanguage(Language),
+    ChangeMistypePolicy(MistypePolicy),
+    ChangeMatchMode(MatchMode),
+    ChangeTypoTolerance(TypoTolerance),
+    ChangeKeyboardLayout(KeyboardLayout),
+    ToggleKeyboardOverlay,
+    ChangeDirectorMode(DirectorMode),
     ChangeMenuState(MenuState),
-    RunGame,
+    StartRun(Difficulty),
+    StartReplay(ReplayMode),
+    StartDrill(Drill),
+    DrillWord,
+    ClearScreen(Entity),
     ChangeTowerPriority(Entity, TowerPriority),
     UpgradeTower(Entity, ArrowTowerUpgrade),
 }
@@ -83,9 +102,19 @@ impl Display for Action {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", match *self {
             Self::SpawnArrow(_) => String::from("Shoot Arrow"),
-            Self::ChangeLanguage(ref language) => format!("{language:?}"),
+            Self::ChangeLanguage(ref language) => language.name.clone(),
+            Self::ChangeMistypePolicy(policy) => format!("{policy}"),
+            Self::ChangeMatchMode(mode) => format!("{mode}"),
+            Self::ChangeTypoTolerance(tolerance) => format!("{tolerance}"),
+            Self::ChangeKeyboardLayout(layout) => format!("{layout}"),
+            Self::ToggleKeyboardOverlay => String::from("Keyboard Overlay"),
+            Self::ChangeDirectorMode(mode) => format!("{mode}"),
             Self::ChangeMenuState(ref menu) => format!("{menu}"),
-            Self::RunGame => String::from("Run Game"),
+            Self::StartRun(difficulty) => format!("{difficulty}"),
+            Self::StartReplay(mode) => format!("{mode}"),
+            Self::StartDrill(drill) => format!("{drill}"),
+            Self::DrillWord => String::from("Drill Word"),
+            Self::ClearScreen(_) => String::from("Clear Screen"),
             Self::ChangeTowerPriority(_, priority) => format!("{priority:?}"),
             Self::UpgradeTower(_, upgrade) => format!("{upgrade}"),
         })
@@ -96,69 +125,671 @@ impl Display for Action {
 #[reflect(Component)]
 pub struct ToType {
     pub word: String,
-    pub progress: usize,
+    /// The typed graphemes. Only the assist mode lets typos into it, otherwise it is the start of
+    /// the word
+    pub typed: String,
+    /// A wrongly typed grapheme that has to be removed with backspace
+    pub error: Option<String>,
+    /// Input that matches the start of the next grapheme, e.g. the first s of ß
+    pub partial: String,
+    /// How often the word was mistyped
+    pub mistakes: u32,
+    /// The typos the assist mode let through when the word was completed
+    pub distance: usize,
     pub action: Action,
 }
 impl ToType {
     pub const fn new(word: String, action: Action) -> Self {
         Self {
             word,
-            progress: 0,
+            typed: String::new(),
+            error: None,
+            partial: String::new(),
+            mistakes: 0,
+            distance: 0,
             action,
         }
     }
+    /// The amount of typed graphemes
+    pub fn progress(&self) -> usize {
+        self.typed.graphemes(true).count()
+    }
+    /// Returns the next grapheme that has to be typed, or None if the word is complete
+    pub fn next_grapheme(&self) -> Option<&str> {
+        self.word.graphemes(true).nth(self.progress())
+    }
+    pub fn is_complete(&self) -> bool {
+        self.progress() >= self.word.graphemes(true).count()
+    }
+    /// Whether the word is partly typed. Without a target, these are the `ToTypes` that all fit
+    /// the graphemes typed so far
+    pub fn in_progress(&self) -> bool {
+        (!self.typed.is_empty() || !self.partial.is_empty()) && !self.is_complete()
+    }
+    /// Whether the word was typed without mistypes, and the assist mode let no typos through
+    pub const fn flawless(&self) -> bool {
+        self.mistakes == 0 && self.distance == 0
+    }
+    /// Starts over from the first grapheme
+    fn restart(&mut self) {
+        self.typed.clear();
+        self.error = None;
+        self.partial.clear();
+    }
+    /// 1 for words completed without typos, lower the more typos the assist mode let through
+    pub fn accuracy(&self) -> f64 {
+        let length = self.word.graphemes(true).count().max(1);
+        (1. - self.distance as f64 / length as f64).max(0.)
+    }
 }
 
-/// Sets the language based on the device language
-fn set_device_language(mut language: ResMut<Language>) {
-    let Some(language_string) = get_lang() else {
-        return;
-    };
-    if language_string.contains("de") {
-        *language = Language::German;
+/// The `ToType` that currently receives all input.
+/// Gets picked by the first correct character, and released when the word is finished,
+/// mistyped or cancelled
+#[derive(Resource, Debug, Clone, Copy, Reflect, Default)]
+#[reflect(Resource)]
+#[repr(transparent)]
+pub struct TypingTarget {
+    pub entity: Option<Entity>,
+}
+
+/// The amount of `ToTypes` completed in a row without a mistype
+#[derive(Resource, Debug, Clone, Copy, Reflect, Default)]
+#[reflect(Resource)]
+#[repr(transparent)]
+pub struct Combo {
+    pub value: u32,
+}
+impl Combo {
+    /// How much each completed `ToType` adds to the multiplier
+    const STEP: f64 = 0.1;
+    const MAX_MULTIPLIER: f64 = 3.;
+
+    /// The factor damage and income get multiplied with
+    pub fn multiplier(self) -> f64 {
+        (1. + f64::from(self.value) * Self::STEP).min(Self::MAX_MULTIPLIER)
     }
 }
 
-/// Handles the input for the `ToTypes`
+fn reset_combo(mut combo: ResMut<Combo>) {
+    combo.value = 0;
+}
+
+/// What happens when the wrong character is typed for the targeted `ToType`
+#[derive(
+    Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect, Default, EnumIter, Serialize, Deserialize,
+)]
+#[reflect(Resource)]
+pub enum MistypePolicy {
+    /// Reset the progress to zero and release the target
+    #[default]
+    Reset,
+    /// Keep the progress and ignore the wrong character
+    Hold,
+    /// Keep the progress, but the wrong character has to be removed with backspace
+    Backspace,
+    /// Keep the progress, but lose some money
+    MoneyPenalty,
+    /// Keep the progress, but typing is blocked for a short time
+    TimePenalty,
+}
+impl MistypePolicy {
+    /// Money lost per mistake with `MoneyPenalty`
+    const MONEY_PENALTY: f64 = 1.;
+    /// Seconds that typing is blocked per mistake with `TimePenalty`
+    const TIME_PENALTY: f64 = 0.5;
+}
+impl Display for MistypePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Self::Reset => "Reset",
+            Self::Hold => "Hold",
+            Self::Backspace => "Backspace",
+            Self::MoneyPenalty => "Money Penalty",
+            Self::TimePenalty => "Time Penalty",
+        })
+    }
+}
+
+/// Whether the player picked the `MistypePolicy`, otherwise the difficulty picks it
+#[derive(Resource, Debug, Clone, Copy, Reflect, Default)]
+#[reflect(Resource)]
+#[repr(transparent)]
+pub struct MistypePolicyChosen {
+    pub value: bool,
+}
+
+/// How typed graphemes are compared to the graphemes of the `ToTypes`
+#[derive(
+    Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect, Default, EnumIter, Serialize, Deserialize,
+)]
+#[reflect(Resource)]
+pub enum MatchMode {
+    /// Graphemes have to be typed exactly
+    #[default]
+    Exact,
+    /// Capital letters can be typed without shift
+    IgnoreCase,
+    /// Ignores case and accents, so words can be typed without the keys of their language
+    /// (ü matches u, ß matches ss)
+    IgnoreAccents,
+}
+impl MatchMode {
+    /// Returns the text in the form it is compared in
+    fn fold(self, text: &str) -> String {
+        match self {
+            Self::Exact => text.to_owned(),
+            Self::IgnoreCase => text.to_lowercase(),
+            Self::IgnoreAccents => {
+                let mut folded = String::new();
+                for character in text
+                    .to_lowercase()
+                    .nfd()
+                    .filter(|&character| !is_combining_mark(character))
+                {
+                    // Letters that don't decompose into a base letter and an accent
+                    match character {
+                        'ß' => folded.push_str("ss"),
+                        'æ' => folded.push_str("ae"),
+                        'œ' => folded.push_str("oe"),
+                        'þ' => folded.push_str("th"),
+                        'ø' => folded.push('o'),
+                        'ł' => folded.push('l'),
+                        'đ' => folded.push('d'),
+                        other => folded.push(other),
+                    }
+                }
+                folded
+            }
+        }
+    }
+    /// Returns whether typing the start of one grapheme also types the start of the other, e.g.
+    /// D and d when ignoring case
+    pub fn ambiguous(self, a: &str, b: &str) -> bool {
+        let a = self.fold(a);
+        let b = self.fold(b);
+        // Exact matching doesn't split graphemes
+        a == b || (self != Self::Exact && (a.starts_with(&b) || b.starts_with(&a)))
+    }
+    /// Compares the typed input to the expected grapheme
+    pub fn compare(self, expected: &str, typed: &str) -> GraphemeMatch {
+        let expected = self.fold(expected);
+        let typed = self.fold(typed);
+        if expected == typed {
+            GraphemeMatch::Full
+        // Exact matching doesn't split graphemes
+        } else if self != Self::Exact && !typed.is_empty() && expected.starts_with(&typed) {
+            GraphemeMatch::Partial
+        } else {
+            GraphemeMatch::Wrong
+        }
+    }
+}
+impl Display for MatchMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match *self {
+            Self::Exact => "Exact",
+            Self::IgnoreCase => "Case Insensitive",
+            Self::IgnoreAccents => "Accent Folding",
+        })
+    }
+}
+
+/// Assist mode, completes words that are typed with at most the given amount of typos.
+/// Typos are counted as the edit distance between the typed graphemes and the word
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect, Default, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[repr(transparent)]
+pub struct TypoTolerance {
+    pub max_distance: usize,
+}
+impl TypoTolerance {
+    /// The tolerances shown in the assist menu
+    pub const OPTIONS: [Self; 3] = [Self::new(0), Self::new(1), Self::new(2)];
+
+    pub const fn new(max_distance: usize) -> Self {
+        Self { max_distance }
+    }
+    pub const fn enabled(self) -> bool {
+        self.max_distance > 0
+    }
+    /// Adds the grapheme to the typed graphemes. Once they are as long as the word, the `ToType`
+    /// is completed if they are close enough to it, otherwise it starts over
+    fn type_grapheme(self, to_type: &mut ToType, grapheme: &str, matching: MatchMode) {
+        to_type.typed.push_str(grapheme);
+        if to_type.progress() >= to_type.word.graphemes(true).count()
+            && !self.submit(to_type, matching)
+        {
+            to_type.typed.clear();
+        }
+    }
+    /// Completes the `ToType` if the typed graphemes are close enough to the word, e.g. when a
+    /// word with skipped graphemes is submitted. Returns whether it was completed
+    fn submit(self, to_type: &mut ToType, matching: MatchMode) -> bool {
+        let word: Vec<_> = to_type.word.graphemes(true).collect();
+        let typed: Vec<_> = to_type.typed.graphemes(true).collect();
+        let distance = edit_distance(&word, &typed, |expected, typed| {
+            matching.compare(expected, typed) == GraphemeMatch::Full
+        });
+
+        let close = !typed.is_empty() && distance <= self.max_distance;
+        if close {
+            to_type.distance = distance;
+            to_type.typed.clone_from(&to_type.word);
+        }
+        close
+    }
+}
+impl Display for TypoTolerance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self.max_distance {
+            0 => String::from("Off"),
+            1 => String::from("1 Typo"),
+            typos => format!("{typos} Typos"),
+        })
+    }
+}
+
+/// Returns the Levenshtein distance between the two grapheme sequences
+fn edit_distance(a: &[&str], b: &[&str], same: impl Fn(&str, &str) -> bool) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, &x) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(!same(x, y));
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphemeMatch {
+    /// The input types the grapheme
+    Full,
+    /// The input types the start of the grapheme, e.g. s for ß
+    Partial,
+    Wrong,
+}
+
+/// Enables IME, so composed characters (dead keys, CJK input, ...) can be typed
+fn enable_ime(mut windows: Query<&mut Window, With<PrimaryWindow>>) {
+    if let Ok(mut window) = windows.get_single_mut() {
+        window.ime_enabled = true;
+    }
+}
+
+/// Input for the `ToTypes`, collected from key presses and IME commits
+#[derive(Debug, Clone, Event, Serialize, Deserialize)]
+pub enum TypedInput {
+    /// Typed text, may contain multiple graphemes
+    Text(String),
+    /// Drop the current target, or submit it in the assist mode
+    Cancel,
+    /// Remove the wrongly typed grapheme
+    Backspace,
+    /// Open or close the pause menu
+    Pause,
+}
+
+/// Seconds in which an IME commit is considered a duplicate of a key press with the same text
+const IME_DUPLICATE_WINDOW: f64 = 0.2;
+
+/// Turns key presses and IME events into `TypedInput`.
+/// Reads the raw winit events, as only they contain the text a key press types, including
+/// characters composed with dead keys
 #[expect(clippy::wildcard_enum_match_arm)]
-fn read_input(mut chars: EventReader<KeyboardInput>, mut to_types: Query<&mut ToType>) {
-    // For each character typed
-    chars
-        .read()
-        .filter(|&event| event.state == ButtonState::Pressed)
-        .for_each(|event| {
-            // Get the actual character
-            let character = match event.logical_key {
-                Key::Character(ref character) => character
-                    .chars()
-                    .next()
-                    .expect("Character should exist if there is an event for it"),
-                Key::Space => ' ',
-                _ => {
-                    return;
+pub fn collect_input(
+    mut window_events: EventReader<RawWinitWindowEvent>,
+    mut ime: EventReader<Ime>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut composing: Local<bool>,
+    mut key_texts: Local<Vec<(String, f64)>>,
+    mut inputs: EventWriter<TypedInput>,
+    time: Res<Time<Real>>,
+) {
+    let now = time.elapsed_secs_f64();
+    // Text of recent key presses, to filter out IME commits that duplicate them
+    key_texts.retain(|&(_, pressed)| now - pressed < IME_DUPLICATE_WINDOW);
+
+    for event in window_events.read() {
+        let WindowEvent::KeyboardInput { ref event, .. } = event.event else {
+            continue;
+        };
+        // Keys pressed during composition belong to the IME
+        if event.state != ElementState::Pressed || *composing {
+            continue;
+        }
+        let input = match event.logical_key {
+            WinitKey::Named(NamedKey::Enter) => TypedInput::Cancel,
+            WinitKey::Named(NamedKey::Backspace) => TypedInput::Backspace,
+            WinitKey::Named(NamedKey::Escape) => TypedInput::Pause,
+            _ => {
+                // Shortcuts like Ctrl+C don't type anything
+                if shortcut_held(&keys) {
+                    continue;
                 }
-            };
+                let Some(text) = event
+                    .text
+                    .as_ref()
+                    .map(|text| text.chars().filter(|character| !character.is_control()))
+                    .map(String::from_iter)
+                    .filter(|text| !text.is_empty())
+                else {
+                    continue;
+                };
+                key_texts.push((text.clone(), now));
+                TypedInput::Text(text)
+            }
+        };
+        inputs.send(input);
+    }
 
-            to_types
-                .iter_mut()
-                // Filter out inactive to_types
-                .for_each(|mut to_type| {
-                    // If the typed character is the next character of the word
-                    if to_type.word.chars().nth(to_type.progress) == Some(character) {
-                        to_type.progress += 1;
-                    // Otherwise reset the progress
+    for event in ime.read() {
+        match *event {
+            Ime::Preedit { ref value, .. } => *composing = !value.is_empty(),
+            Ime::Commit { ref value, .. } => {
+                *composing = false;
+                // Some platforms send both a key press and a commit for the same text
+                if let Some(index) = key_texts.iter().position(|(text, _)| text == value) {
+                    key_texts.remove(index);
+                } else {
+                    inputs.send(TypedInput::Text(value.clone()));
+                }
+            }
+            Ime::Enabled { .. } | Ime::Disabled { .. } => *composing = false,
+        }
+    }
+}
+
+/// Whether a modifier for shortcuts is held.
+/// Alt isn't one, as it types symbols on macOS (e.g. @ and { with Option on German layouts).
+/// `AltGr` is reported as Ctrl + Alt on some platforms, and still types text
+fn shortcut_held(keys: &ButtonInput<KeyCode>) -> bool {
+    let alt_gr = keys.pressed(KeyCode::AltRight);
+    keys.any_pressed([KeyCode::SuperLeft, KeyCode::SuperRight])
+        || (!alt_gr && keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]))
+}
+
+/// Handles the input for the `ToTypes`
+/// Graphemes go to every `ToType` they fit, until only one is left. Then it is locked onto, and all
+/// following graphemes only go to it
+#[expect(clippy::too_many_arguments)]
+pub fn read_input(
+    mut inputs: EventReader<TypedInput>,
+    mut to_types: Query<(Entity, &mut ToType)>,
+    mut target: ResMut<TypingTarget>,
+    mut stats: ResMut<TypingStats>,
+    mut money: ResMut<Money>,
+    mut combo: ResMut<Combo>,
+    mut blocked_until: Local<f64>,
+    policy: Res<MistypePolicy>,
+    matching: Res<MatchMode>,
+    tolerance: Res<TypoTolerance>,
+    game_state: Res<State<GameState>>,
+    time: Res<Time>,
+) {
+    // Only the words of a run count towards the combo, not menu buttons or drills
+    let in_run = *game_state.get() == GameState::Running;
+    // Menu buttons aren't part of the typing stats
+    let in_menu = *game_state.get() == GameState::Menu;
+
+    for input in inputs.read() {
+        let text = match *input {
+            TypedInput::Text(ref text) => text,
+            TypedInput::Cancel => {
+                // Drop the ambiguous candidates as well
+                if target.entity.is_none() {
+                    for (_, mut to_type) in &mut to_types {
+                        if to_type.in_progress() {
+                            to_type.restart();
+                        }
+                    }
+                }
+                if let Some(entity) = target.entity.take()
+                    && let Ok((_, mut to_type)) = to_types.get_mut(entity)
+                {
+                    to_type.partial.clear();
+                    // The assist mode can't tell when a word with skipped graphemes is finished
+                    if tolerance.enabled() && tolerance.submit(&mut to_type, *matching) {
+                        if in_run && to_type.flawless() {
+                            combo.value += 1;
+                        }
                     } else {
-                        to_type.progress = 0;
+                        to_type.typed.clear();
+                        to_type.error = None;
+                    }
+                }
+                continue;
+            }
+            TypedInput::Backspace => {
+                if let Some(entity) = target.entity
+                    && let Ok((_, mut to_type)) = to_types.get_mut(entity)
+                {
+                    // The assist mode removes typos like a text field
+                    if to_type.error.take().is_none() && tolerance.enabled() {
+                        let last = to_type
+                            .typed
+                            .grapheme_indices(true)
+                            .next_back()
+                            .map_or(0, |(index, _)| index);
+                        to_type.typed.truncate(last);
+                    }
+                }
+                continue;
+            }
+            TypedInput::Pause => continue,
+        };
+
+        // For each grapheme typed
+        for grapheme in text.graphemes(true) {
+            // Ignore input while a time penalty is active
+            if time.elapsed_secs_f64() < *blocked_until {
+                continue;
+            }
+
+            // Release the target if it doesnt exist anymore (completed or despawned)
+            if let Some(entity) = target.entity
+                && !to_types.contains(entity)
+            {
+                target.entity = None;
+            }
+
+            // Lock onto the only to_type that fits the graphemes typed so far. The ones in
+            // progress are the candidates, or every to_type if nothing has been typed yet
+            let entity = if let Some(entity) = target.entity {
+                entity
+            } else {
+                let any_in_progress = to_types.iter().any(|(_, to_type)| to_type.in_progress());
+                let mut candidates = Vec::new();
+                for (entity, mut to_type) in &mut to_types {
+                    if any_in_progress && !to_type.in_progress() {
+                        continue;
+                    }
+                    let fits = to_type.next_grapheme().is_some_and(|next| {
+                        let typed = format!("{}{grapheme}", to_type.partial);
+                        matching.compare(next, &typed) != GraphemeMatch::Wrong
+                    });
+                    if fits {
+                        candidates.push(entity);
+                    } else if to_type.in_progress() {
+                        to_type.restart();
                     }
+                }
+
+                match *candidates.as_slice() {
+                    [entity] => entity,
+                    [] => {
+                        // Nothing fits the typed grapheme
+                        if in_run {
+                            combo.value = 0;
+                        }
+                        if !in_menu {
+                            stats.record(Keystroke {
+                                grapheme: grapheme.to_owned(),
+                                expected: None,
+                                correct: false,
+                                to_type: None,
+                                action: None,
+                                time: time.elapsed_secs_f64(),
+                            });
+                        }
+                        continue;
+                    }
+                    // Still ambiguous, so the grapheme goes to all of them
+                    _ => {
+                        let mut expected = None;
+                        for &entity in &candidates {
+                            let (_, mut to_type) = to_types
+                                .get_mut(entity)
+                                .expect("Candidate should exist, as it was just found");
+                            let next = to_type.next_grapheme().unwrap_or_default().to_owned();
+                            let typed = format!("{}{grapheme}", to_type.partial);
+                            if matching.compare(&next, &typed) == GraphemeMatch::Partial {
+                                to_type.partial = typed;
+                            } else {
+                                to_type.partial.clear();
+                                to_type.typed.push_str(&next);
+                            }
+                            expected.get_or_insert(next);
+                        }
+                        // A completed to_type ends the ambiguity, e.g. "Ignore" while "Ignored"
+                        // still fits
+                        if candidates.iter().any(|&entity| {
+                            to_types
+                                .get(entity)
+                                .is_ok_and(|(_, to_type)| to_type.is_complete())
+                        }) {
+                            for (_, mut to_type) in &mut to_types {
+                                if to_type.in_progress() {
+                                    to_type.restart();
+                                }
+                            }
+                        }
+                        if !in_menu {
+                            stats.record(Keystroke {
+                                grapheme: grapheme.to_owned(),
+                                expected,
+                                correct: true,
+                                to_type: None,
+                                action: None,
+                                time: time.elapsed_secs_f64(),
+                            });
+                        }
+                        continue;
+                    }
+                }
+            };
+
+            let (_, mut to_type) = to_types
+                .get_mut(entity)
+                .expect("Target should exist, as it was checked above");
+
+            let expected = to_type.next_grapheme().map(str::to_owned);
+            let typed = format!("{}{grapheme}", to_type.partial);
+            let result = match expected {
+                // Nothing is correct until a pending error is removed
+                Some(ref expected) if to_type.error.is_none() => matching.compare(expected, &typed),
+                _ => GraphemeMatch::Wrong,
+            };
+            let correct = result != GraphemeMatch::Wrong;
+            if !in_menu {
+                stats.record(Keystroke {
+                    grapheme: grapheme.to_owned(),
+                    expected: expected.clone(),
+                    correct,
+                    to_type: Some(entity),
+                    action: Some(to_type.action.clone()),
+                    time: time.elapsed_secs_f64(),
                 });
-        });
+            }
+
+            if tolerance.enabled() {
+                // Wait for the rest of the grapheme
+                if result == GraphemeMatch::Partial {
+                    to_type.partial = typed;
+                    target.entity = Some(entity);
+                    continue;
+                }
+                to_type.partial.clear();
+                if !correct {
+                    to_type.mistakes += 1;
+                    if in_run {
+                        combo.value = 0;
+                    }
+                }
+                // Matched graphemes are stored as the word has them, so they count as typed
+                let typed = match expected {
+                    Some(expected) if correct => expected,
+                    _ => typed,
+                };
+                tolerance.type_grapheme(&mut to_type, &typed, *matching);
+                // Release the target once the word is complete, or has to be started over
+                target.entity =
+                    (!to_type.is_complete() && !to_type.typed.is_empty()).then_some(entity);
+                if in_run && to_type.is_complete() && to_type.flawless() {
+                    combo.value += 1;
+                }
+                continue;
+            }
+
+            // Wait for the rest of the grapheme
+            if result == GraphemeMatch::Partial {
+                to_type.partial = typed;
+                target.entity = Some(entity);
+            // If the typed grapheme is the next grapheme of the word
+            } else if correct {
+                to_type.partial.clear();
+                to_type
+                    .typed
+                    .push_str(expected.as_deref().unwrap_or_default());
+                // Release the target once the word is complete
+                target.entity = (!to_type.is_complete()).then_some(entity);
+                if in_run && to_type.is_complete() && to_type.flawless() {
+                    combo.value += 1;
+                }
+            } else {
+                to_type.mistakes += 1;
+                if in_run {
+                    combo.value = 0;
+                }
+                match *policy {
+                    MistypePolicy::Reset => {
+                        to_type.typed.clear();
+                        to_type.partial.clear();
+                        target.entity = None;
+                    }
+                    MistypePolicy::Hold => target.entity = Some(entity),
+                    MistypePolicy::Backspace => {
+                        to_type.error.get_or_insert_with(|| grapheme.to_owned());
+                        target.entity = Some(entity);
+                    }
+                    MistypePolicy::MoneyPenalty => {
+                        money.value = (money.value - MistypePolicy::MONEY_PENALTY).max(0.);
+                        target.entity = Some(entity);
+                    }
+                    MistypePolicy::TimePenalty => {
+                        *blocked_until = time.elapsed_secs_f64() + MistypePolicy::TIME_PENALTY;
+                        target.entity = Some(entity);
+                    }
+                }
+            }
+        }
+    }
 }
 
 /// Executes the actions of any completed `ToTypes`, despawns them afterwards
-fn handle_to_types(query: Query<(&ToType, &Parent, Entity)>, mut commands: Commands) {
+pub fn handle_to_types(
+    query: Query<(&ToType, &Parent, Entity)>,
+    mut commands: Commands,
+    layout: Res<KeyboardLayout>,
+    scoring: Res<LayoutScoring>,
+) {
     for (to_type, parent, entity) in &query {
-        if to_type.progress >= to_type.word.chars().count() {
-            handle_action(to_type.action.clone(), &mut commands);
+        if to_type.is_complete() {
+            let reward = scoring.reward(*layout, &to_type.word) * to_type.accuracy();
+            handle_action(to_type.action.clone(), reward, &mut commands);
 
             // Despawn entity
             commands.entity(parent.get()).remove_children(&[entity]);
@@ -167,11 +798,31 @@ fn handle_to_types(query: Query<(&ToType, &Parent, Entity)>, mut commands: Comma
     }
 }
 
-pub fn handle_action(action: Action, commands: &mut Commands<'_, '_>) {
+/// Executes the action, the reward scales its effect (e.g. the damage of arrows)
+pub fn handle_action(action: Action, reward: f64, commands: &mut Commands<'_, '_>) {
     match action {
-        Action::SpawnArrow(tower) => commands.trigger_targets(SpawnArrow, tower),
+        Action::SpawnArrow(tower) => commands.trigger_targets(SpawnArrow(reward), tower),
         Action::ChangeLanguage(language) => commands.trigger(ChangeLanguage(language)),
-        Action::RunGame => commands.trigger(RunGame),
+        Action::ChangeMistypePolicy(policy) => commands.trigger(ChangeMistypePolicy(policy)),
+        Action::ChangeMatchMode(mode) => commands.trigger(ChangeMatchMode(mode)),
+        Action::ChangeTypoTolerance(tolerance) => {
+            commands.trigger(ChangeTypoTolerance(tolerance));
+        }
+        Action::ChangeKeyboardLayout(layout) => commands.trigger(ChangeKeyboardLayout(layout)),
+        Action::ToggleKeyboardOverlay => commands.trigger(ToggleKeyboardOverlay),
+        Action::ChangeDirectorMode(mode) => commands.trigger(ChangeDirectorMode(mode)),
+        Action::StartRun(difficulty) => {
+            commands.trigger(ChangeDifficulty(difficulty));
+            commands.trigger(ChangeReplayMode(ReplayMode::Record));
+            commands.trigger(RunGame);
+        }
+        Action::StartReplay(mode) => {
+            commands.trigger(ChangeReplayMode(mode));
+            commands.trigger(RunGame);
+        }
+        Action::StartDrill(drill) => commands.trigger(StartDrill(drill)),
+        Action::DrillWord => commands.trigger(DrillWordTyped),
+        Action::ClearScreen(ability) => commands.trigger_targets(ClearScreen, ability),
         Action::ChangeMenuState(state) => commands.trigger(ChangeMenuState(state)),
         Action::ChangeTowerPriority(tower, priority) => {
             commands.trigger_targets(ChangeTowerPriority(priority), tower);
@@ -183,67 +834,196 @@ pub fn handle_action(action: Action, commands: &mut Commands<'_, '_>) {
 }
 
 #[derive(Debug, Clone, Event)]
-pub struct ChangeLanguage(Language);
+pub struct ChangeMistypePolicy(MistypePolicy);
+
+pub fn change_mistype_policy(
+    trigger: Trigger<ChangeMistypePolicy>,
+    mut policy: ResMut<MistypePolicy>,
+    mut chosen: ResMut<MistypePolicyChosen>,
+) {
+    *policy = trigger.event().0;
+    chosen.value = true;
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct ChangeMatchMode(MatchMode);
+
+pub fn change_match_mode(trigger: Trigger<ChangeMatchMode>, mut mode: ResMut<MatchMode>) {
+    *mode = trigger.event().0;
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct ChangeTypoTolerance(TypoTolerance);
 
-pub fn change_language(trigger: Trigger<ChangeLanguage>, mut language: ResMut<Language>) {
-    *language = trigger.event().0;
+pub fn change_typo_tolerance(
+    trigger: Trigger<ChangeTypoTolerance>,
+    mut tolerance: ResMut<TypoTolerance>,
+) {
+    *tolerance = trigger.event().0;
 }
 
 #[derive(Debug, Clone, Event)]
-pub struct AddToType(pub Action, pub Option<String>);
+pub struct AddToType(pub Action, pub WordSource);
+
+/// Where the word of a `ToType` comes from
+#[derive(Debug, Clone)]
+pub enum WordSource {
+    /// A fixed word, used for menu buttons
+    Fixed(String),
+    /// A random word of the current language
+    Random(WordDifficulty),
+    /// A random phrase of the current language, used for big actions
+    Phrase,
+}
+
+/// Width at which phrases get wrapped
+const PHRASE_WIDTH: f32 = 500.;
+
+/// How words are drawn
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct WordStyle {
+    /// Replaces the default font, languages with their own font keep using it
+    pub font: Option<Handle<Font>>,
+    pub font_size: f32,
+    /// The box behind words on the map, keeps them readable over the tiles
+    pub background: Color,
+    /// Space between a word and the edge of its background
+    pub padding: f32,
+    /// Seconds a word flashes red and shakes after a mistype
+    pub mistype_duration: f64,
+    /// How far a shaking word moves to each side
+    pub shake_distance: f32,
+}
+impl Default for WordStyle {
+    fn default() -> Self {
+        Self {
+            font: None,
+            font_size: 25.,
+            background: Color::srgba(0., 0., 0., 0.5),
+            padding: 4.,
+            mistype_duration: 0.3,
+            shake_distance: 4.,
+        }
+    }
+}
+impl WordStyle {
+    /// Shakes per second
+    const SHAKE_SPEED: f64 = 40.;
+    /// Height of the caret, relative to the font size
+    const CARET_HEIGHT: f32 = 0.1;
+}
+
+/// The order of the text spans of a `ToType`
+const TYPED_SPAN: usize = 0;
+const ERROR_SPAN: usize = 1;
+const NEXT_SPAN: usize = 2;
+const CURRENT_SPAN: usize = 3;
+const UPCOMING_SPAN: usize = 4;
 
+/// Marks the next grapheme of a word on the map
+#[derive(Component, Debug, Clone, Reflect, Default)]
+#[reflect(Component)]
+struct WordCaret;
+
+#[derive(Component, Debug, Clone, Reflect, Default)]
+#[reflect(Component)]
+struct WordBackground;
+
+/// Lets a `ToType` flash red and shake after a mistype
+#[derive(Component, Debug, Clone, Reflect, Default)]
+#[reflect(Component)]
+struct MistypeFeedback {
+    /// The mistakes of the `ToType` that were already shown
+    mistakes: u32,
+    /// Elapsed seconds until which the mistype is shown
+    until: f64,
+}
+
+#[expect(clippy::too_many_arguments)]
 pub fn add_to_type(
     trigger: Trigger<AddToType>,
     mut commands: Commands,
-    wordlists: Res<Assets<Wordlists>>,
+    mut word_picker: WordPicker,
     handles: Res<Handles>,
+    style: Res<WordStyle>,
     language: Res<Language>,
+    // Menu buttons aren't on the map
+    to_types: Query<&ToType, With<Text2d>>,
     game_state: Res<State<GameState>>,
 ) {
-    let AddToType(ref action, ref option_word) = *trigger.event();
-
-    let word = match *option_word {
-        Some(ref word) => word.clone(),
-        None => wordlists
-            .get(&handles.wordlists)
-            .expect("Wordlists should be loaded")
-            .get_word(*language)
-            .replace("ß", "ss"),
+    let AddToType(ref action, ref source) = *trigger.event();
+
+    // Avoid the prefixes of all words on the map
+    let taken: Vec<_> = to_types
+        .iter()
+        .map(|to_type| to_type.word.as_str())
+        .collect();
+    let word = match *source {
+        WordSource::Fixed(ref word) => word.clone(),
+        WordSource::Random(difficulty) => word_picker.pick_word(difficulty, &taken),
+        WordSource::Phrase => word_picker.pick_phrase(&taken),
+    };
+    // Generated words might need the font of their language
+    let font = match language.font {
+        Some(ref font) if !matches!(*source, WordSource::Fixed(_)) => font.clone(),
+        _ => style.font.clone().unwrap_or_else(|| handles.font.clone()),
     };
 
     commands.entity(trigger.entity()).with_children(|parent| {
         let mut entity = parent.spawn((
             Name::new("Text"),
             ToType::new(word.clone(), action.clone()),
+            MistypeFeedback::default(),
             TextLayout::default(),
             Layer::new(3.),
         ));
+        // Typed, error, next grapheme, rest of the current word, upcoming words
         entity.with_children(|parent| {
-            parent.spawn((
-                TextSpan::new(String::new()),
-                TextFont {
-                    font: handles.font.clone(),
-                    font_size: 25.,
-                    ..default()
-                },
-                TextColor(Color::Srgba(GREEN)),
-            ));
-            parent.spawn((
-                TextSpan::new(String::new()),
-                TextFont {
-                    font: handles.font.clone(),
-                    font_size: 25.,
-                    ..default()
-                },
-                TextColor(Color::WHITE),
-            ));
+            for color in [
+                Color::Srgba(GREEN),
+                Color::Srgba(RED),
+                Color::WHITE,
+                Color::WHITE,
+                Color::Srgba(LIGHT_GRAY),
+            ] {
+                parent.spawn((
+                    TextSpan::new(String::new()),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: style.font_size,
+                        ..default()
+                    },
+                    TextColor(color),
+                ));
+            }
         });
 
         // If the to type is going to be in a menu, use a different bundle
-        if option_word.is_some() {
+        if let WordSource::Fixed(_) = *source {
             entity.insert(Text::new(String::new()));
         } else {
-            entity.insert(Text2d::new(String::new()));
+            entity
+                .insert(Text2d::new(String::new()))
+                // Sized and placed once the text is laid out
+                .with_children(|parent| {
+                    parent.spawn((
+                        Name::new("Caret"),
+                        WordCaret,
+                        Sprite::from_color(Color::WHITE, Vec2::ZERO),
+                        Transform::from_xyz(0., 0., 0.1),
+                    ));
+                    parent.spawn((
+                        Name::new("Background"),
+                        WordBackground,
+                        Sprite::from_color(style.background, Vec2::ZERO),
+                        Transform::from_xyz(0., 0., -0.1),
+                    ));
+                });
+        }
+        // Wrap phrases, so they don't cover the whole map
+        if let WordSource::Phrase = *source {
+            entity.insert(TextBounds::new_horizontal(PHRASE_WIDTH));
         }
         if *game_state.get() != GameState::Menu {
             entity.insert(StateScoped(*game_state.get()));
@@ -255,9 +1035,154 @@ pub fn add_to_type(
 }
 
 /// Changes character color based on word completion
-fn handle_text_display(query: Query<(&ToType, Entity), Changed<ToType>>, mut writer: TextUiWriter) {
-    for (to_type, text) in &query {
-        *writer.text(text, 0) = to_type.word.chars().take(to_type.progress).collect();
-        *writer.text(text, 1) = to_type.word.chars().skip(to_type.progress).collect();
+/// Typed graphemes are green, a pending error is red, the rest of the current word is white and
+/// the upcoming words of a phrase are gray. The next grapheme gets its own span for the caret
+fn handle_text_display(
+    query: Query<(&ToType, &Children), Changed<ToType>>,
+    mut spans: Query<&mut TextSpan>,
+) {
+    for (to_type, children) in &query {
+        let rest: String = to_type
+            .word
+            .graphemes(true)
+            .skip(to_type.progress())
+            .collect();
+        // The current word ends before the next space
+        let split = rest
+            .char_indices()
+            .skip(1)
+            .find(|&(_, character)| character == ' ')
+            .map_or(rest.len(), |(index, _)| index);
+        let (current, upcoming) = rest.split_at(split);
+        let next = current.graphemes(true).next().unwrap_or_default();
+
+        let mut texts = [const { String::new() }; 5];
+        texts[TYPED_SPAN] = to_type
+            .word
+            .graphemes(true)
+            .take(to_type.progress())
+            .collect();
+        texts[ERROR_SPAN] = to_type.error.clone().unwrap_or_default();
+        texts[NEXT_SPAN] = String::from(next);
+        texts[CURRENT_SPAN] = String::from(&current[next.len()..]);
+        texts[UPCOMING_SPAN] = String::from(upcoming);
+        for (&child, text) in children.iter().zip(texts) {
+            if let Ok(mut span) = spans.get_mut(child) {
+                **span = text;
+            }
+        }
+    }
+}
+
+/// Places the caret under the next grapheme, and fits the background to the text
+fn update_word_decorations(
+    words: Query<
+        (&TextLayoutInfo, &ComputedTextBlock, &Anchor, &Children),
+        (With<ToType>, Changed<TextLayoutInfo>),
+    >,
+    mut carets: Query<
+        (&mut Transform, &mut Sprite, &mut Visibility),
+        (With<WordCaret>, Without<WordBackground>),
+    >,
+    mut backgrounds: Query<(&mut Transform, &mut Sprite), With<WordBackground>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    style: Res<WordStyle>,
+) {
+    // Glyphs are positioned in physical pixels, the size of the text in logical pixels
+    let scale_factor = windows
+        .get_single()
+        .map_or(1., |window| window.resolution.scale_factor());
+
+    for (layout_info, text_block, anchor, children) in &words {
+        // The text is laid out from its bottom left corner
+        let origin = layout_info.size * -(anchor.as_vec() + 0.5);
+        let next_span = children.get(NEXT_SPAN).and_then(|&span| {
+            text_block
+                .entities()
+                .iter()
+                .position(|text_entity| text_entity.entity == span)
+        });
+        // Spaces have no glyph, so the caret is hidden while one is next
+        let next_glyph = layout_info
+            .glyphs
+            .iter()
+            .find(|glyph| Some(glyph.span_index) == next_span);
+
+        for &child in children.iter() {
+            if let Ok((mut transform, mut sprite, mut visibility)) = carets.get_mut(child) {
+                if let Some(glyph) = next_glyph {
+                    let position = origin + glyph.position / scale_factor;
+                    let size = glyph.size / scale_factor;
+                    let height = style.font_size * WordStyle::CARET_HEIGHT;
+                    transform.translation.x = position.x;
+                    transform.translation.y = position.y - size.y / 2. - height;
+                    sprite.custom_size = Some(Vec2::new(size.x, height));
+                    *visibility = Visibility::Inherited;
+                } else {
+                    *visibility = Visibility::Hidden;
+                }
+            } else if let Ok((mut transform, mut sprite)) = backgrounds.get_mut(child) {
+                // Center the background on the text, whatever its anchor
+                let center = -anchor.as_vec() * layout_info.size;
+                transform.translation.x = center.x;
+                transform.translation.y = center.y;
+                sprite.custom_size =
+                    (layout_info.size != Vec2::ZERO).then(|| layout_info.size + 2. * style.padding);
+            }
+        }
+    }
+}
+
+/// Flashes the rest of a mistyped word red, and shakes it if it is on the map
+fn show_mistypes(
+    mut to_types: Query<(
+        &ToType,
+        &mut MistypeFeedback,
+        &mut Transform,
+        &Children,
+        Has<Text2d>,
+    )>,
+    mut colors: Query<&mut TextColor>,
+    style: Res<WordStyle>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs_f64();
+
+    for (to_type, mut feedback, mut transform, children, on_map) in &mut to_types {
+        if to_type.mistakes > feedback.mistakes {
+            feedback.mistakes = to_type.mistakes;
+            feedback.until = now + style.mistype_duration;
+        }
+        let remaining = feedback.until - now;
+        let active = remaining > 0.;
+
+        let color = if active {
+            Color::Srgba(RED)
+        } else {
+            Color::WHITE
+        };
+        for &span in [NEXT_SPAN, CURRENT_SPAN]
+            .iter()
+            .filter_map(|&index| children.get(index))
+        {
+            if let Ok(mut text_color) = colors.get_mut(span)
+                && text_color.0 != color
+            {
+                text_color.0 = color;
+            }
+        }
+
+        // The transform of menu words belongs to the ui layout
+        if on_map {
+            let shake = if active {
+                (now * WordStyle::SHAKE_SPEED).sin() * remaining / style.mistype_duration
+            } else {
+                0.
+            };
+            let x = style.shake_distance * shake as f32;
+            if transform.translation.x != x {
+                transform.translation.x = x;
+            }
+        }
     }
 }
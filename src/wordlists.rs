@@ -0,0 +1,775 @@
+use std::collections::{BTreeMap, VecDeque};
+#[cfg(not(target_family = "wasm"))]
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+use bevy_device_lang::get_lang;
+use rand::{Rng, distributions::WeightedIndex, prelude::Distribution};
+use serde::Deserialize;
+use strum::{EnumCount, EnumIter, IntoEnumIterator};
+use unicode_segmentation::UnicodeSegmentation;
+
+#[cfg(not(target_family = "wasm"))]
+use crate::asset_loader::data_dir;
+use crate::{
+    asset_loader::Handles,
+    keyboard::{KeyboardLayout, LayoutScoring},
+    replay::{Recording, ReplayedWords},
+    rng::{GameRng, RngStream},
+    states::{GameState, MenuState},
+    stats::TypingStats,
+    typing::MatchMode,
+};
+
+pub struct WordlistPlugin;
+impl Plugin for WordlistPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Language>()
+            .init_resource::<Languages>()
+            .init_resource::<UniquePrefixes>()
+            .init_resource::<FrequencyWeighting>()
+            .init_resource::<AdaptiveWords>()
+            .init_resource::<WordHistory>()
+            .register_type::<Language>()
+            .register_type::<Languages>()
+            .register_type::<UniquePrefixes>()
+            .register_type::<FrequencyWeighting>()
+            .register_type::<AdaptiveWords>()
+            .register_type::<WordHistory>()
+            .add_systems(
+                OnExit(GameState::Loading),
+                (load_languages, set_device_language.after(load_languages)),
+            )
+            .add_systems(OnExit(MenuState::MainMenu), reset_word_history)
+            .add_observer(change_language);
+
+        // The web build has no file system to load custom wordlists from
+        #[cfg(not(target_family = "wasm"))]
+        app.add_systems(
+            OnExit(GameState::Loading),
+            load_custom_wordlists.before(load_languages),
+        );
+    }
+}
+
+/// A wordlist and its metadata, as stored in the wordlists asset
+#[derive(Deserialize, Debug)]
+pub struct Wordlist {
+    /// The name shown in the language menu
+    pub name: String,
+    /// Locale codes (e.g. "de"), used to pick the language based on the device language
+    #[serde(default)]
+    pub locales: Vec<String>,
+    /// Path to a font that can display the words, the default font is used if there is none
+    #[serde(default)]
+    pub font: Option<String>,
+    /// The font, if it was loaded from outside the assets folder
+    #[serde(skip)]
+    font_handle: Option<Handle<Font>>,
+    pub words: Vec<String>,
+    /// Quotes or short sentences, used for big actions
+    #[serde(default)]
+    pub phrases: Vec<String>,
+    /// The words aren't sorted by frequency (e.g. keywords), so they are all equally likely
+    #[serde(default)]
+    pub unordered: bool,
+    #[serde(skip)]
+    index: WordIndex,
+}
+impl Wordlist {
+    /// Returns the weight of the word with the rank, based on its frequency
+    fn frequency_weight(&self, weighting: &FrequencyWeighting, rank: usize) -> f64 {
+        if self.unordered {
+            1.
+        } else {
+            weighting.weight(rank)
+        }
+    }
+    /// Computes the `WordIndex`, has to be called after the words change
+    fn build_index(&mut self) {
+        let lengths: Vec<usize> = self
+            .words
+            .iter()
+            .map(|word| word.graphemes(true).count())
+            .collect();
+
+        for difficulty in WordDifficulty::iter() {
+            // Sorted lists are sorted by frequency, so the index is the rank
+            let mut tier: Vec<usize> = (0..self.words.len())
+                .filter(|&rank| {
+                    let position = (!self.unordered).then(|| rank as f64 / self.words.len() as f64);
+                    difficulty.contains(position, lengths[rank])
+                })
+                .collect();
+            // Small lists might not have words of every difficulty
+            if tier.is_empty() {
+                tier = (0..self.words.len()).collect();
+            }
+            self.index.tiers[difficulty as usize] = tier;
+        }
+
+        for layout in KeyboardLayout::iter() {
+            self.index.layout_difficulties[layout as usize] = self
+                .words
+                .iter()
+                .map(|word| layout.difficulty(word))
+                .collect();
+        }
+    }
+}
+
+/// What is known about the words of a wordlist, computed once when it is loaded so picking a word
+/// doesn't have to look at every word
+#[derive(Debug, Default)]
+struct WordIndex {
+    /// The ranks of the words of each `WordDifficulty`
+    tiers: [Vec<usize>; WordDifficulty::COUNT],
+    /// How hard each word is to type on each `KeyboardLayout`
+    layout_difficulties: [Vec<f64>; KeyboardLayout::COUNT],
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Wordlist {
+    /// Parses a plain text wordlist, with one word per line.
+    /// Lines starting with `#` are comments, except for the `name`, `language` (comma separated
+    /// locale codes), `font` (relative to the wordlist, or to the assets folder) and `unordered`
+    /// (`true` if the words aren't sorted by frequency) headers, e.g. `# name: Rust keywords`.
+    /// Returns None if there are no words
+    pub fn parse_txt(default_name: &str, text: &str) -> Option<Self> {
+        let mut wordlist = Self {
+            name: String::from(default_name),
+            locales: Vec::new(),
+            font: None,
+            font_handle: None,
+            words: Vec::new(),
+            phrases: Vec::new(),
+            unordered: false,
+            index: WordIndex::default(),
+        };
+
+        for line in text.lines().map(str::trim) {
+            if let Some(comment) = line.strip_prefix('#') {
+                let Some((key, value)) = comment.split_once(':') else {
+                    continue;
+                };
+                let value = value.trim();
+                match key.trim() {
+                    "name" => wordlist.name = String::from(value),
+                    "language" => {
+                        wordlist.locales = value
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|locale| !locale.is_empty())
+                            .map(String::from)
+                            .collect();
+                    }
+                    "font" => wordlist.font = Some(String::from(value)),
+                    "unordered" => wordlist.unordered = value == "true",
+                    _ => {}
+                }
+            } else if !line.is_empty() {
+                wordlist.words.push(String::from(line));
+            }
+        }
+
+        (!wordlist.words.is_empty()).then_some(wordlist)
+    }
+}
+
+/// All wordlists, keyed by the id of their language
+#[derive(Default, Deserialize, Asset, Debug, TypePath)]
+#[serde(transparent)]
+pub struct Wordlists {
+    pub lists: BTreeMap<String, Wordlist>,
+}
+impl Wordlists {
+    /// Returns the inputted Language's wordlist
+    /// Falls back to the first wordlist if the language doesn't have one
+    fn wordlist(&self, language: &Language) -> &Wordlist {
+        self.lists
+            .get(&language.id)
+            .or_else(|| self.lists.values().next())
+            .expect("There should be at least one wordlist")
+    }
+}
+
+/// Picks words and phrases for `ToTypes` from the current language's wordlist
+#[derive(SystemParam)]
+pub struct WordPicker<'w> {
+    wordlists: Res<'w, Assets<Wordlists>>,
+    handles: Res<'w, Handles>,
+    language: Res<'w, Language>,
+    unique_prefixes: Res<'w, UniquePrefixes>,
+    matching: Res<'w, MatchMode>,
+    weighting: Res<'w, FrequencyWeighting>,
+    adaptive: Res<'w, AdaptiveWords>,
+    stats: Res<'w, TypingStats>,
+    layout: Res<'w, KeyboardLayout>,
+    layout_scoring: Res<'w, LayoutScoring>,
+    history: ResMut<'w, WordHistory>,
+    rng: ResMut<'w, GameRng>,
+    recording: Option<ResMut<'w, Recording>>,
+    replayed: Option<ResMut<'w, ReplayedWords>>,
+    time: Res<'w, Time>,
+}
+impl WordPicker<'_> {
+    /// The amount of words in a phrase made up from random words
+    const GENERATED_PHRASE_LENGTH: usize = 5;
+
+    fn wordlist(&self) -> &Wordlist {
+        self.wordlists
+            .get(&self.handles.wordlists)
+            .expect("Wordlists should be loaded")
+            .wordlist(&self.language)
+    }
+    /// Returns a random word of the given difficulty, whose prefix isn't the prefix of any of the
+    /// taken words, and that wasn't picked recently.
+    /// More frequent words, and words with the players weak graphemes are more likely.
+    /// Depending on the layout scoring, words that are hard to type on the keyboard layout are
+    /// more or less likely. Replays get the words of their recording instead
+    pub fn pick_word(&mut self, difficulty: WordDifficulty, taken: &[&str]) -> String {
+        let word = self
+            .replayed_word()
+            .unwrap_or_else(|| self.draw_word(difficulty, taken));
+        self.record_word(&word);
+        word
+    }
+    /// Returns a random phrase of the current language, that wasn't picked recently and whose
+    /// prefix isn't the prefix of any of the taken words.
+    /// Joins random words if the language doesn't have any phrases.
+    /// Replays get the phrases of their recording instead
+    pub fn pick_phrase(&mut self, taken: &[&str]) -> String {
+        let phrase = self
+            .replayed_word()
+            .unwrap_or_else(|| self.draw_phrase(taken));
+        self.record_word(&phrase);
+        phrase
+    }
+    /// Returns the next word of the replayed recording
+    fn replayed_word(&mut self) -> Option<String> {
+        let replayed = self.replayed.as_mut()?;
+        let word = replayed.next();
+        if word.is_none() {
+            warn!("The replay ran out of recorded words");
+        }
+        word
+    }
+    fn record_word(&mut self, word: &str) {
+        if let Some(ref mut recording) = self.recording {
+            recording.record_word(word);
+        }
+    }
+    /// Picks a new word, see `pick_word`
+    fn draw_word(&mut self, difficulty: WordDifficulty, taken: &[&str]) -> String {
+        let now = self.time.elapsed_secs_f64();
+        self.history.forget(now);
+
+        let candidates = self.candidates(difficulty, taken);
+
+        let weaknesses = if self.adaptive.strength > 0. {
+            self.stats.weaknesses()
+        } else {
+            HashMap::new()
+        };
+        let wordlist = self.wordlist();
+        let words = &wordlist.words;
+        let layout_difficulties = &wordlist.index.layout_difficulties[*self.layout as usize];
+        let weights: Vec<f64> = candidates
+            .iter()
+            .map(|&rank| {
+                let mut weight = wordlist.frequency_weight(&self.weighting, rank)
+                    * self.adaptive.boost(&words[rank], &weaknesses);
+                if self.layout_scoring.selection != 0. {
+                    weight *= self
+                        .layout_scoring
+                        .selection_weight(layout_difficulties[rank]);
+                }
+                weight
+            })
+            .collect();
+
+        let rng = self.rng.stream(RngStream::Words);
+        // Every candidate might be weighted out
+        let index =
+            pick_weighted(&weights, rng).unwrap_or_else(|| rng.gen_range(0..candidates.len()));
+        let rank = candidates[index];
+        let word = self.wordlist().words[rank].clone();
+        self.history.push(word.clone(), now);
+        word
+    }
+    /// Returns the ranks of the words that fit the difficulty, the history and the unique prefixes
+    fn candidates(&self, difficulty: WordDifficulty, taken: &[&str]) -> Vec<usize> {
+        let wordlist = self.wordlist();
+        let words = &wordlist.words;
+        let all: Vec<usize> = (0..words.len()).collect();
+        let tier = wordlist.index.tiers[difficulty as usize].clone();
+
+        let mut fresh: Vec<usize> = tier
+            .iter()
+            .copied()
+            .filter(|&rank| !self.history.contains(&words[rank]))
+            .collect();
+        // Small lists might run out of words that weren't picked recently
+        if fresh.is_empty() {
+            fresh.clone_from(&tier);
+        }
+
+        self.unique_prefixes
+            .filter(words, &[fresh, tier, all], taken, *self.matching)
+    }
+    /// Picks a new phrase, see `pick_phrase`
+    fn draw_phrase(&mut self, taken: &[&str]) -> String {
+        let now = self.time.elapsed_secs_f64();
+        self.history.forget(now);
+
+        let wordlist = self.wordlist();
+        let phrase = if wordlist.phrases.is_empty() {
+            let weights: Vec<f64> = (0..wordlist.words.len())
+                .map(|rank| wordlist.frequency_weight(&self.weighting, rank))
+                .collect();
+            // Only the first word decides which ToType the phrase collides with
+            let first = self.candidates(WordDifficulty::Any, taken);
+            let first_weights: Vec<f64> = first.iter().map(|&rank| weights[rank]).collect();
+
+            let rng = self.rng.stream(RngStream::Words);
+            let mut ranks = vec![
+                first[pick_weighted(&first_weights, rng)
+                    .unwrap_or_else(|| rng.gen_range(0..first.len()))],
+            ];
+            ranks.extend((1..Self::GENERATED_PHRASE_LENGTH).map(|_| {
+                pick_weighted(&weights, rng).unwrap_or_else(|| rng.gen_range(0..weights.len()))
+            }));
+
+            let words = &self.wordlist().words;
+            ranks
+                .into_iter()
+                .map(|rank| words[rank].as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else {
+            let all: Vec<usize> = (0..wordlist.phrases.len()).collect();
+            let mut fresh: Vec<usize> = all
+                .iter()
+                .copied()
+                .filter(|&index| !self.history.contains(&wordlist.phrases[index]))
+                .collect();
+            // Every phrase was picked recently
+            if fresh.is_empty() {
+                fresh.clone_from(&all);
+            }
+            let candidates = self.unique_prefixes.filter(
+                &wordlist.phrases,
+                &[fresh, all],
+                taken,
+                *self.matching,
+            );
+            let index = candidates[self
+                .rng
+                .stream(RngStream::Words)
+                .gen_range(0..candidates.len())];
+            self.wordlist().phrases[index].clone()
+        };
+
+        self.history.push(phrase.clone(), now);
+        phrase
+    }
+}
+
+/// Picks an index, weighted by the weights.
+/// Returns None if there are no weights, or none of them is positive
+fn pick_weighted(weights: &[f64], rng: &mut impl Rng) -> Option<usize> {
+    WeightedIndex::new(weights)
+        .ok()
+        .map(|index| index.sample(rng))
+}
+
+/// Keeps the starts of simultaneously visible words unique, so typing them is never ambiguous
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct UniquePrefixes {
+    /// The amount of graphemes at the start of a word that have to be unique, 0 disables the check
+    pub length: usize,
+    /// What to do if no word has a unique prefix
+    pub fallback: PrefixFallback,
+}
+impl Default for UniquePrefixes {
+    fn default() -> Self {
+        Self {
+            length: 1,
+            fallback: PrefixFallback::Relax,
+        }
+    }
+}
+impl UniquePrefixes {
+    /// Returns the indices of the texts from the first pool that don't collide with the taken
+    /// words, when typed with the match mode. The pools go from the most to the least fitting
+    /// texts, the first one is used if every text collides
+    fn filter(
+        &self,
+        texts: &[String],
+        pools: &[Vec<usize>],
+        taken: &[&str],
+        matching: MatchMode,
+    ) -> Vec<usize> {
+        let Some(first) = pools.first() else {
+            return Vec::new();
+        };
+        if self.length == 0 || taken.is_empty() {
+            return first.clone();
+        }
+
+        let pools = match self.fallback {
+            PrefixFallback::Relax => pools,
+            PrefixFallback::Any => &pools[..1],
+        };
+        for pool in pools {
+            let unique: Vec<usize> = pool
+                .iter()
+                .copied()
+                .filter(|&index| {
+                    !taken
+                        .iter()
+                        .any(|word| self.collides(&texts[index], word, matching))
+                })
+                .collect();
+            if !unique.is_empty() {
+                return unique;
+            }
+        }
+
+        match self.fallback {
+            // Every text collides, so take the ones that share the least with the taken words
+            PrefixFallback::Relax => {
+                let shared = |index: usize| {
+                    taken
+                        .iter()
+                        .map(|word| shared_prefix(&texts[index], word, matching))
+                        .max()
+                        .unwrap_or_default()
+                };
+                let least = first.iter().map(|&index| shared(index)).min();
+                first
+                    .iter()
+                    .copied()
+                    .filter(|&index| Some(shared(index)) == least)
+                    .collect()
+            }
+            PrefixFallback::Any => first.clone(),
+        }
+    }
+    /// Returns whether the prefixes of the texts can't be told apart when typed, or one text is
+    /// the start of the other
+    fn collides(&self, text: &str, word: &str, matching: MatchMode) -> bool {
+        text.graphemes(true)
+            .zip(word.graphemes(true))
+            .take(self.length)
+            .all(|(a, b)| matching.ambiguous(a, b))
+    }
+}
+
+/// Returns how many graphemes at the start of the texts can't be told apart when typed
+fn shared_prefix(a: &str, b: &str, matching: MatchMode) -> usize {
+    a.graphemes(true)
+        .zip(b.graphemes(true))
+        .take_while(|&(a, b)| matching.ambiguous(a, b))
+        .count()
+}
+
+#[derive(Debug, Clone, Copy, Reflect)]
+pub enum PrefixFallback {
+    /// Ignore the history, and then the difficulty, until a word is found. If there still is
+    /// none, use the words that share the fewest graphemes with the taken words
+    Relax,
+    /// Use any word
+    Any,
+}
+
+/// How difficult a word should be, based on its frequency rank and length
+#[derive(Debug, Clone, Copy, Default, Reflect, EnumCount, EnumIter)]
+pub enum WordDifficulty {
+    #[default]
+    Any,
+    /// Frequent, short words
+    Easy,
+    Medium,
+    /// Rare, long words
+    Hard,
+}
+impl WordDifficulty {
+    /// Returns whether a word belongs to the difficulty
+    /// `position` is the position of the word in the frequency sorted list, between 0 and 1, or
+    /// None if the list isn't sorted. `length` is its amount of graphemes
+    fn contains(self, position: Option<f64>, length: usize) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Easy => position.is_none_or(|position| position < 0.3) && length <= 5,
+            Self::Medium => {
+                position.is_none_or(|position| position < 0.7) && (4..=8).contains(&length)
+            }
+            Self::Hard => position.is_none_or(|position| position >= 0.3) && length >= 7,
+        }
+    }
+}
+
+/// Makes frequent words more likely, by weighting each word with 1 / rank^exponent
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+#[repr(transparent)]
+pub struct FrequencyWeighting {
+    /// 0 picks every word with the same probability
+    pub exponent: f64,
+}
+impl Default for FrequencyWeighting {
+    fn default() -> Self {
+        Self { exponent: 0.5 }
+    }
+}
+impl FrequencyWeighting {
+    /// Returns the weight of the word with the rank
+    fn weight(&self, rank: usize) -> f64 {
+        (rank as f64 + 1.).powf(-self.exponent)
+    }
+}
+
+/// Recently picked words and phrases, which won't be picked again for a while.
+/// Shared by everything that picks words
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct WordHistory {
+    /// A word is blocked until this many other words were picked, 0 disables this
+    pub draws: usize,
+    /// A word is blocked for this many seconds, 0 disables this
+    pub seconds: f64,
+    /// The blocked words, and when they were picked
+    words: VecDeque<(String, f64)>,
+}
+impl Default for WordHistory {
+    fn default() -> Self {
+        Self {
+            draws: 20,
+            seconds: 10.,
+            words: VecDeque::new(),
+        }
+    }
+}
+impl WordHistory {
+    /// Forgets the words that aren't blocked anymore
+    fn forget(&mut self, now: f64) {
+        while self.words.len() > self.draws
+            && self
+                .words
+                .front()
+                .is_some_and(|&(_, time)| now - time >= self.seconds)
+        {
+            self.words.pop_front();
+        }
+    }
+    fn contains(&self, word: &str) -> bool {
+        self.words.iter().any(|(blocked, _)| blocked == word)
+    }
+    fn push(&mut self, word: String, now: f64) {
+        self.words.push_back((word, now));
+    }
+}
+
+/// Clears the history, so runs with the same seed pick the same words
+fn reset_word_history(mut history: ResMut<WordHistory>) {
+    history.words.clear();
+}
+
+/// Makes words with graphemes the player often misses or types slowly more likely
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+#[repr(transparent)]
+pub struct AdaptiveWords {
+    /// 0 ignores the players weaknesses
+    pub strength: f64,
+}
+impl Default for AdaptiveWords {
+    fn default() -> Self {
+        Self { strength: 1. }
+    }
+}
+impl AdaptiveWords {
+    /// Returns the factor the weight of the word gets multiplied with
+    fn boost(&self, word: &str, weaknesses: &HashMap<String, f64>) -> f64 {
+        if weaknesses.is_empty() {
+            return 1.;
+        }
+        let (total, count) = word
+            .graphemes(true)
+            .fold((0., 0.), |(total, count), grapheme| {
+                (
+                    total + weaknesses.get(grapheme).copied().unwrap_or_default(),
+                    count + 1.,
+                )
+            });
+        if count == 0. {
+            return 1.;
+        }
+        1. + self.strength * total / count
+    }
+}
+
+/// The currently selected language
+#[derive(Resource, Debug, Clone, Reflect, Default)]
+#[reflect(Resource)]
+pub struct Language {
+    /// The key of the wordlist in the wordlists asset
+    pub id: String,
+    pub name: String,
+    pub locales: Vec<String>,
+    pub font: Option<Handle<Font>>,
+}
+
+/// All languages that have a wordlist
+#[derive(Resource, Debug, Clone, Reflect, Default)]
+#[reflect(Resource)]
+#[repr(transparent)]
+pub struct Languages {
+    pub value: Vec<Language>,
+}
+
+/// The directory custom `.txt` wordlists are loaded from.
+/// Can be set with `TYPINGTD_WORDLISTS`, defaults to the platforms data directory
+#[cfg(not(target_family = "wasm"))]
+fn custom_wordlist_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("TYPINGTD_WORDLISTS") {
+        return Some(PathBuf::from(dir));
+    }
+
+    data_dir().map(|data_dir| data_dir.join("wordlists"))
+}
+
+/// Adds the `.txt` wordlists from the custom wordlist directory to the loaded wordlists
+#[cfg(not(target_family = "wasm"))]
+fn load_custom_wordlists(
+    mut wordlists: ResMut<Assets<Wordlists>>,
+    mut fonts: ResMut<Assets<Font>>,
+    handles: Res<Handles>,
+) {
+    let Some(dir) = custom_wordlist_dir() else {
+        return;
+    };
+    // Not having custom wordlists is fine
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return;
+    };
+
+    let wordlists = wordlists
+        .get_mut(&handles.wordlists)
+        .expect("Wordlists should be loaded");
+
+    for path in entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+    {
+        let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error) => {
+                warn!("Couldn't read wordlist {}: {error}", path.display());
+                continue;
+            }
+        };
+        match Wordlist::parse_txt(stem, &text) {
+            // Prefix the id, so custom wordlists don't replace the built in ones
+            Some(mut wordlist) => {
+                // The asset server can only load fonts from the assets folder
+                if let Some(font_path) = wordlist
+                    .font
+                    .as_ref()
+                    .map(|font| dir.join(font))
+                    .filter(|font_path| font_path.is_file())
+                {
+                    wordlist.font_handle = load_font_file(&font_path, &mut fonts);
+                    wordlist.font = None;
+                }
+                wordlists.lists.insert(format!("custom/{stem}"), wordlist);
+            }
+            None => warn!("Wordlist {} doesn't contain any words", path.display()),
+        }
+    }
+}
+
+/// Loads a font from outside the assets folder
+#[cfg(not(target_family = "wasm"))]
+fn load_font_file(path: &Path, fonts: &mut Assets<Font>) -> Option<Handle<Font>> {
+    let bytes = fs::read(path)
+        .inspect_err(|error| warn!("Couldn't read font {}: {error}", path.display()))
+        .ok()?;
+    let font = Font::try_from_bytes(bytes)
+        .inspect_err(|error| warn!("Couldn't parse font {}: {error}", path.display()))
+        .ok()?;
+    Some(fonts.add(font))
+}
+
+/// Fills `Languages` with the languages of the loaded wordlists, and indexes their words.
+/// Wordlists without words are removed, as no words could be picked from them
+fn load_languages(
+    mut languages: ResMut<Languages>,
+    mut wordlists: ResMut<Assets<Wordlists>>,
+    handles: Res<Handles>,
+    asset_server: Res<AssetServer>,
+) {
+    let wordlists = wordlists
+        .get_mut(&handles.wordlists)
+        .expect("Wordlists should be loaded");
+
+    wordlists.lists.retain(|id, wordlist| {
+        wordlist.words.retain(|word| !word.trim().is_empty());
+        wordlist.phrases.retain(|phrase| !phrase.trim().is_empty());
+        if wordlist.words.is_empty() {
+            warn!("Wordlist {id} doesn't contain any words");
+        }
+        !wordlist.words.is_empty()
+    });
+    for wordlist in wordlists.lists.values_mut() {
+        wordlist.build_index();
+    }
+
+    languages.value = wordlists
+        .lists
+        .iter()
+        .map(|(id, wordlist)| Language {
+            id: id.clone(),
+            name: wordlist.name.clone(),
+            locales: wordlist.locales.clone(),
+            font: wordlist
+                .font_handle
+                .clone()
+                .or_else(|| wordlist.font.as_ref().map(|font| asset_server.load(font))),
+        })
+        .collect();
+}
+
+/// Sets the language based on the device language, or to the first one if none matches
+fn set_device_language(mut language: ResMut<Language>, languages: Res<Languages>) {
+    let device_language = get_lang();
+
+    let matching = device_language.and_then(|device_language| {
+        languages.value.iter().find(|language| {
+            language
+                .locales
+                .iter()
+                .any(|locale| device_language.starts_with(locale.as_str()))
+        })
+    });
+
+    if let Some(new_language) = matching.or_else(|| languages.value.first()) {
+        *language = new_language.clone();
+    }
+}
+
+#[derive(Debug, Clone, Event)]
+pub struct ChangeLanguage(pub Language);
+
+pub fn change_language(trigger: Trigger<ChangeLanguage>, mut language: ResMut<Language>) {
+    *language = trigger.event().0.clone();
+}